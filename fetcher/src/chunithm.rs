@@ -5,15 +5,17 @@ use async_trait::async_trait;
 
 use otoge::chunithm::models::{DataStore, Song, SongFromAPI};
 use otoge::chunithm::{ChunithmIntl, ChunithmJP};
-use otoge::shared::traits::Otoge;
+use otoge::shared::traits::{DataStore as DataStoreTrait, Otoge};
 
 use crate::extractors::serde::SerdeGetExtractor;
+use crate::http::HttpClient;
+use crate::safeguard::Safeguard;
 use crate::traits::FetchTask;
 
 trait Chunithm {
     fn impl_verify_categories(data_store: &DataStore) -> Result<()> {
         let categories = &data_store.categories;
-        let songs = &data_store.songs;
+        let songs = data_store.available_songs();
 
         let all_categories: HashSet<&str> = categories.iter().map(|c| c.name.as_ref()).collect();
 
@@ -46,8 +48,15 @@ impl FetchTask<Self> for ChunithmJP {
         "https://chunithm.sega.jp/storage/json/music.json"
     }
 
+    fn safeguard() -> Safeguard {
+        Safeguard {
+            min_count: 800,
+            ..Safeguard::default()
+        }
+    }
+
     async fn verify_categories(
-        _client: &reqwest::Client,
+        _client: &HttpClient,
         data_store: &<Self as Otoge>::DataStore,
     ) -> Result<()> {
         Self::impl_verify_categories(data_store)
@@ -68,8 +77,15 @@ impl FetchTask<Self> for ChunithmIntl {
         "https://chunithm.sega.com/assets/data/music.json"
     }
 
+    fn safeguard() -> Safeguard {
+        Safeguard {
+            min_count: 700,
+            ..Safeguard::default()
+        }
+    }
+
     async fn verify_categories(
-        _client: &reqwest::Client,
+        _client: &HttpClient,
         data_store: &<Self as Otoge>::DataStore,
     ) -> Result<()> {
         Self::impl_verify_categories(data_store)
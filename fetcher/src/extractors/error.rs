@@ -0,0 +1,56 @@
+use std::fmt::{self, Display};
+
+use scraper::ElementRef;
+
+/// Maximum length of the HTML snippet attached to an [`ExtractionError`]
+const SNIPPET_LENGTH: usize = 300;
+
+/// Failure to extract a value from a scraped page, located precisely enough to find the
+/// offending markup
+#[derive(Debug)]
+pub struct ExtractionError {
+    pub page_num: usize,
+    /// Index of the song entry on the page, if the failure is specific to one entry
+    pub entry_index: Option<usize>,
+    pub selector: &'static str,
+    pub message: String,
+    pub snippet: String,
+}
+
+impl ExtractionError {
+    pub fn new(
+        page_num: usize,
+        entry_index: Option<usize>,
+        selector: &'static str,
+        element: ElementRef<'_>,
+        message: impl Into<String>,
+    ) -> Self {
+        Self {
+            page_num,
+            entry_index,
+            selector,
+            message: message.into(),
+            snippet: snippet(&element.html()),
+        }
+    }
+}
+
+impl Display for ExtractionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} on page {}", self.message, self.page_num)?;
+        if let Some(entry_index) = self.entry_index {
+            write!(f, ", entry {entry_index}")?;
+        }
+        write!(f, " (selector `{}`), near: {}", self.selector, self.snippet)
+    }
+}
+
+impl std::error::Error for ExtractionError {}
+
+fn snippet(html: &str) -> String {
+    let html = html.split_whitespace().collect::<Vec<_>>().join(" ");
+    match html.char_indices().nth(SNIPPET_LENGTH) {
+        Some((end, _)) => format!("{}...", &html[..end]),
+        None => html,
+    }
+}
@@ -1,4 +1,6 @@
+pub mod error;
 pub mod polarischord;
 pub mod popnmusic;
+pub mod scrape;
 pub mod serde;
 pub mod soundvoltex_vii;
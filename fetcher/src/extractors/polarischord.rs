@@ -1,6 +1,7 @@
 use async_trait::async_trait;
 use serde::{Deserialize, de::DeserializeOwned};
 
+use crate::http::HttpClient;
 use crate::traits::{Extractor, FetchTask};
 use otoge::{polarischord::models::APIInput, shared::traits::Otoge};
 
@@ -28,12 +29,17 @@ where
     G::Song: std::convert::From<G::ApiSong>,
     G::ApiSong: DeserializeOwned + std::fmt::Debug,
 {
-    async fn fetch_songs(client: &reqwest::Client) -> anyhow::Result<Vec<G::Song>> {
+    async fn fetch_songs(client: &HttpClient) -> anyhow::Result<Vec<G::Song>> {
         let input_data = APIInput::default();
         let url = G::api_url();
 
-        let resp = client.post(url).form(&input_data).send().await?;
-        let data = resp.json::<APIResponse<G::ApiSong>>().await?;
+        let resp = client
+            .post(url)
+            .form(&input_data)
+            .send()
+            .await?
+            .error_for_status()?;
+        let data = resp.json::<APIResponse<G::ApiSong>>()?;
 
         Ok(data
             .data
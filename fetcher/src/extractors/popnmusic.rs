@@ -1,20 +1,114 @@
-use std::borrow::Cow;
 use std::collections::HashMap;
 use std::sync::Arc;
 
 use async_trait::async_trait;
-use scraper::Html;
-use scraper::Selector;
-use scraper::error::SelectorErrorKind;
-use tokio::sync::Semaphore;
 use tokio::task::JoinSet;
 use tracing::{Instrument, info, info_span};
 
+use crate::extractors::scrape::{
+    CountSpec, EntrySpec, FetchedPage, FieldSpec, FilterSpec, LevelSpec, PageMethod, Pagination,
+    ScrapeSpec, ScrapedEntry, Scraper, Value,
+};
+use crate::http::HttpClient;
 use crate::traits::{Extractor, FetchTask};
 use otoge::popnmusic::models::{Category, LevelMap, Song};
 use otoge::popnmusic::{get_all_bemani, get_all_categories, get_all_versions};
 use otoge::shared::traits::Otoge;
 
+const TEXT: Value = Value::Text {
+    separator: "",
+    trim: false,
+};
+
+/// Every song entry spans three list items: jacket, song info and levels
+pub static SPEC: ScrapeSpec = ScrapeSpec {
+    url: "https://p.eagate.573.jp/game/popn/popn29/music/list.html",
+    pagination: Pagination {
+        method: PageMethod::Get,
+        page_param: "page",
+        first_page: 0,
+        page_options: r#"select#s_page > option"#,
+    },
+    params: &[
+        ("lv", "0"),
+        ("keyword", ""),
+        ("sort", "music"),
+        ("sort_type", "none"),
+    ],
+    entry: EntrySpec {
+        selector: r#"ul.mu_list_table:not(.mu_head) > li"#,
+        group: 3,
+        fields: &[
+            FieldSpec {
+                name: "image_id",
+                part: 0,
+                selector: r#"img"#,
+                index: 0,
+                value: Value::Attr("src"),
+            },
+            FieldSpec {
+                name: "genre",
+                part: 1,
+                selector: r#"p"#,
+                index: 0,
+                value: TEXT,
+            },
+            FieldSpec {
+                name: "title",
+                part: 1,
+                selector: r#"p"#,
+                index: 1,
+                value: TEXT,
+            },
+            FieldSpec {
+                name: "artist",
+                part: 1,
+                selector: r#"p"#,
+                index: 2,
+                value: TEXT,
+            },
+        ],
+        lists: &[],
+        levels: Some(LevelSpec {
+            part: 2,
+            selector: r#"p"#,
+            difficulty: Value::Child(r#"span"#, &TEXT),
+            level: Value::Attr("data-d"),
+            difficulties: &[
+                ("LIGHT", "light"),
+                ("NORMAL", "normal"),
+                ("HYPER", "hyper"),
+                ("EX", "ex"),
+            ],
+            skip_classes: &[],
+            skip_values: &["-"],
+        }),
+        // Genre, title and artist
+        counts: &[CountSpec {
+            part: 1,
+            selector: r#"p"#,
+            count: 3,
+        }],
+    },
+    filters: &[
+        FilterSpec {
+            param: "version",
+            options: r#"select#s_version > option"#,
+            any: "-1",
+        },
+        FilterSpec {
+            param: "bemani",
+            options: r#"select#s_bemani > option"#,
+            any: "0",
+        },
+        FilterSpec {
+            param: "category",
+            options: r#"select#s_cate > option"#,
+            any: "0",
+        },
+    ],
+};
+
 pub struct PopNMusicExtractor;
 
 #[async_trait]
@@ -24,32 +118,23 @@ where
     G::Song: Send,
     Vec<<G as Otoge>::Song>: FromIterator<Song>,
 {
-    async fn fetch_songs(client: &reqwest::Client) -> anyhow::Result<Vec<G::Song>> {
-        let selectors = Arc::new(
-            Selectors::init()
-                .map_err(|e| anyhow::anyhow!("Failed to initialize CSS selectors: {e}"))?,
-        );
-
-        let url = G::api_url();
-        let semaphore = Arc::new(Semaphore::new(10));
+    async fn fetch_songs(client: &HttpClient) -> anyhow::Result<Vec<G::Song>> {
+        let scraper = Arc::new(Scraper::new(&SPEC)?);
 
         info!("Fetching all songs");
 
-        let all_songs_filter = PageFilter::no_filter();
+        let all_songs_params = scraper.filter_params(None);
 
-        let all_songs_first_page = {
-            let _permit = Arc::clone(&semaphore).acquire_owned().await.unwrap();
-            get_page_content(client, url, 0, &all_songs_filter).await?
-        };
+        let all_songs_first_page = scraper
+            .fetch_page(client, scraper.first_page(), &all_songs_params)
+            .await?;
 
-        verify_filter_options(&all_songs_first_page.html_string, &selectors)?;
+        verify_filter_options(&scraper.filter_options(&all_songs_first_page))?;
 
         let all_songs = fetch_pages_for_filter(
             client.clone(),
-            url,
-            all_songs_filter,
-            Arc::clone(&selectors),
-            Arc::clone(&semaphore),
+            Arc::clone(&scraper),
+            all_songs_params,
             Some(all_songs_first_page),
         )
         .instrument(info_span!("fetch_all"))
@@ -78,20 +163,14 @@ where
         for version in get_all_versions() {
             let version_id = version.id.as_ref().to_owned();
             let client = client.clone();
-            let selectors = Arc::clone(&selectors);
-            let semaphore = Arc::clone(&semaphore);
+            let scraper = Arc::clone(&scraper);
             let span = info_span!("fetch_version", id = version_id);
 
             joinset.spawn(
                 async move {
-                    let filter = PageFilter {
-                        version: version_id.into(),
-                        ..PageFilter::no_filter()
-                    };
+                    let params = scraper.filter_params(Some(("version", &version_id)));
 
-                    let songs =
-                        fetch_pages_for_filter(client, url, filter, selectors, semaphore, None)
-                            .await?;
+                    let songs = fetch_pages_for_filter(client, scraper, params, None).await?;
 
                     Ok(FilterResult::Version(version, songs))
                 }
@@ -102,20 +181,14 @@ where
         for bemani in all_bemani {
             let bemani_id = bemani.id.as_ref().to_owned();
             let client = client.clone();
-            let selectors = Arc::clone(&selectors);
-            let semaphore = Arc::clone(&semaphore);
+            let scraper = Arc::clone(&scraper);
             let span = info_span!("fetch_bemani", id = bemani_id);
 
             joinset.spawn(
                 async move {
-                    let filter = PageFilter {
-                        bemani: bemani_id.into(),
-                        ..PageFilter::no_filter()
-                    };
+                    let params = scraper.filter_params(Some(("bemani", &bemani_id)));
 
-                    let songs =
-                        fetch_pages_for_filter(client, url, filter, selectors, semaphore, None)
-                            .await?;
+                    let songs = fetch_pages_for_filter(client, scraper, params, None).await?;
 
                     Ok(FilterResult::Bemani(bemani, songs))
                 }
@@ -126,20 +199,14 @@ where
         for category in all_categories {
             let category_id = category.id.as_ref().to_owned();
             let client = client.clone();
-            let selectors = Arc::clone(&selectors);
-            let semaphore = Arc::clone(&semaphore);
+            let scraper = Arc::clone(&scraper);
             let span = info_span!("fetch_recommendations", id = category_id);
 
             joinset.spawn(
                 async move {
-                    let filter = PageFilter {
-                        category: category_id.into(),
-                        ..PageFilter::no_filter()
-                    };
+                    let params = scraper.filter_params(Some(("category", &category_id)));
 
-                    let songs =
-                        fetch_pages_for_filter(client, url, filter, selectors, semaphore, None)
-                            .await?;
+                    let songs = fetch_pages_for_filter(client, scraper, params, None).await?;
 
                     Ok(FilterResult::RecommendationCategory(category, songs))
                 }
@@ -195,10 +262,15 @@ where
             let key = SongKey::from_parsed(&parsed);
 
             songs.push(Song {
+                // Assigned from the id registry once all songs are fetched
+                id: String::new(),
                 image_id: parsed.image_id,
                 genre: parsed.genre,
                 title: parsed.title,
                 artist: parsed.artist,
+                // Tracked across fetches from the local song list
+                first_seen: None,
+                removed_at: None,
                 levels: parsed.levels,
                 version: version_map.get(&key).cloned(),
                 bemani: bemani_map.get(&key).cloned().unwrap_or_default(),
@@ -245,281 +317,177 @@ struct ParsedSong {
     levels: LevelMap,
 }
 
-struct PageFilter {
-    version: Cow<'static, str>,
-    bemani: Cow<'static, str>,
-    category: Cow<'static, str>,
-}
-
-impl PageFilter {
-    // Sentinel values for "no filter":
-    // version="-1" means all versions, bemani/category="0" means all.
-    fn no_filter() -> Self {
+impl From<ScrapedEntry> for ParsedSong {
+    fn from(mut entry: ScrapedEntry) -> Self {
         Self {
-            version: "-1".into(),
-            bemani: "0".into(),
-            category: "0".into(),
+            image_id: entry.take("image_id"),
+            genre: entry.take("genre"),
+            title: entry.take("title"),
+            artist: entry.take("artist"),
+            levels: entry.levels.into_iter().collect(),
         }
     }
 }
 
-struct FetchedPage {
-    page_num: usize,
-    html_string: String,
-}
-
-struct Selectors {
-    list_items: Selector,
-    page_select_options: Selector,
-    version_options: Selector,
-    bemani_options: Selector,
-    category_options: Selector,
-    item_image: Selector,
-    item_info: Selector,
-    item_level_span: Selector,
-}
+/// Checks the site doesn't list versions or categories we don't know
+fn verify_filter_options(options: &HashMap<&'static str, Vec<String>>) -> anyhow::Result<()> {
+    let known_ids = [
+        ("version", get_all_versions()),
+        ("bemani", get_all_bemani()),
+        ("category", get_all_categories()),
+    ];
+
+    for (param, known) in known_ids {
+        let unknown: Vec<&str> = options
+            .get(param)
+            .into_iter()
+            .flatten()
+            .map(String::as_str)
+            .filter(|id| !known.iter().any(|category| category.id == *id))
+            .collect();
 
-impl Selectors {
-    fn init<'a>() -> Result<Self, SelectorErrorKind<'a>> {
-        Ok(Self {
-            list_items: Selector::parse(r#"ul.mu_list_table:not(.mu_head) > li"#)?,
-            page_select_options: Selector::parse(r#"select#s_page > option"#)?,
-            version_options: Selector::parse(r#"select#s_version > option"#)?,
-            bemani_options: Selector::parse(r#"select#s_bemani > option"#)?,
-            category_options: Selector::parse(r#"select#s_cate > option"#)?,
-            item_image: Selector::parse(r#"img"#)?,
-            item_info: Selector::parse(r#"p"#)?,
-            item_level_span: Selector::parse(r#"span"#)?,
-        })
+        anyhow::ensure!(
+            unknown.is_empty(),
+            "Unknown {} ids on site: {:?}",
+            param,
+            unknown
+        );
     }
-}
-
-fn verify_filter_options(html: &str, selectors: &Selectors) -> anyhow::Result<()> {
-    use std::collections::HashSet;
-
-    let all_versions = get_all_versions();
-    let known_version_ids: HashSet<&str> = all_versions.iter().map(|v| v.id.as_ref()).collect();
-
-    let all_bemani = get_all_bemani();
-    let known_bemani_ids: HashSet<&str> = all_bemani.iter().map(|b| b.id.as_ref()).collect();
-
-    let all_categories = get_all_categories();
-    let known_category_ids: HashSet<&str> = all_categories.iter().map(|c| c.id.as_ref()).collect();
-
-    let document = Html::parse_document(html);
-
-    let unknown_versions: Vec<&str> = document
-        .select(&selectors.version_options)
-        .filter_map(|option| option.value().attr("value"))
-        .filter(|id| *id != "-1" && !known_version_ids.contains(id)) // "-1" = all versions sentinel
-        .collect();
-
-    anyhow::ensure!(
-        unknown_versions.is_empty(),
-        "Unknown version ids on site: {:?}",
-        unknown_versions
-    );
-
-    let unknown_bemani: Vec<&str> = document
-        .select(&selectors.bemani_options)
-        .filter_map(|option| option.value().attr("value"))
-        .filter(|id| *id != "0" && !known_bemani_ids.contains(id)) // "0" = no filter sentinel
-        .collect();
-
-    anyhow::ensure!(
-        unknown_bemani.is_empty(),
-        "Unknown bemani ids on site: {:?}",
-        unknown_bemani
-    );
-
-    let unknown_categories: Vec<&str> = document
-        .select(&selectors.category_options)
-        .filter_map(|option| option.value().attr("value"))
-        .filter(|id| *id != "0" && !known_category_ids.contains(id))
-        .collect();
-
-    anyhow::ensure!(
-        unknown_categories.is_empty(),
-        "Unknown recommendation category ids on site: {:?}",
-        unknown_categories
-    );
 
     Ok(())
 }
 
 async fn fetch_pages_for_filter(
-    client: reqwest::Client,
-    base_url: &'static str,
-    filter: PageFilter,
-    selectors: Arc<Selectors>,
-    semaphore: Arc<Semaphore>,
+    client: HttpClient,
+    scraper: Arc<Scraper>,
+    params: Vec<(&'static str, String)>,
     prefetched_first_page: Option<FetchedPage>,
 ) -> anyhow::Result<Vec<ParsedSong>> {
-    let first_page = match prefetched_first_page {
-        Some(page) => page,
-        None => {
-            let _permit = Arc::clone(&semaphore).acquire_owned().await.unwrap();
-            get_page_content(&client, base_url, 0, &filter).await?
-        }
-    };
-
-    let last_page = parse_for_last_page(&first_page.html_string, &selectors)?;
-    info!("Got {} total pages", last_page + 1);
-
-    let mut pages: Vec<(usize, Vec<ParsedSong>)> = vec![(
-        0,
-        parse_songs_from_page(&first_page.html_string, &selectors)?,
-    )];
-
-    let mut joinset = JoinSet::new();
-
-    for page_num in 1..=last_page {
-        let sem = Arc::clone(&semaphore);
-        let client = client.clone();
-        let version = filter.version.clone();
-        let bemani = filter.bemani.clone();
-        let category = filter.category.clone();
+    let entries = scraper
+        .fetch_all(&client, &params, prefetched_first_page)
+        .await?;
 
-        let span = tracing::Span::current();
+    Ok(entries.into_iter().map(ParsedSong::from).collect())
+}
 
-        joinset.spawn(
-            async move {
-                let _permit = sem.acquire_owned().await.unwrap();
+#[cfg(test)]
+mod tests {
+    use crate::extractors::scrape::EntryParser;
 
-                let filter = PageFilter {
-                    version,
-                    bemani,
-                    category,
-                };
+    use super::*;
 
-                get_page_content(&client, base_url, page_num, &filter).await
-            }
-            .instrument(span),
-        );
+    fn page(info: &str) -> String {
+        format!(
+            r#"<ul class="mu_list_table"><li><img src="/jacket.png"></li><li>{info}</li><li>
+            <p data-d="5"><span>LIGHT</span></p></li></ul>"#
+        )
     }
 
-    while let Some(result) = joinset.join_next().await {
-        let fetched = result??;
-        pages.push((
-            fetched.page_num,
-            parse_songs_from_page(&fetched.html_string, &selectors)?,
-        ));
+    #[test]
+    fn rejects_entries_without_three_info_items() {
+        let parser = EntryParser::new(&SPEC.entry).unwrap();
+
+        let entries = parser
+            .parse_entries(0, &page("<p>Genre</p><p>Title</p><p>Artist</p>"))
+            .unwrap();
+        assert_eq!(entries.len(), 1);
+
+        let err = parser
+            .parse_entries(0, &page("<p>Title</p><p>Artist</p>"))
+            .err()
+            .unwrap();
+        assert_eq!(err.entry_index, Some(0));
+        assert_eq!(err.message, "Expected 3 elements, got 2");
+
+        let err = parser
+            .parse_entries(
+                0,
+                &page("<p>Genre</p><p>Title</p><p>Artist</p><p>Extra</p>"),
+            )
+            .err()
+            .unwrap();
+        assert_eq!(err.message, "Expected 3 elements, got 4");
     }
 
-    pages.sort_by_key(|(page_num, _)| *page_num);
-
-    Ok(pages.into_iter().flat_map(|(_, songs)| songs).collect())
-}
-
-async fn get_page_content(
-    client: &reqwest::Client,
-    base_url: &str,
-    page_num: usize,
-    filter: &PageFilter,
-) -> anyhow::Result<FetchedPage> {
-    info!("Fetching page {}", page_num + 1);
-
-    let resp = client
-        .get(base_url)
-        .query(&[
-            ("page", page_num.to_string().as_str()),
-            ("version", filter.version.as_ref()),
-            ("lv", "0"),
-            ("bemani", filter.bemani.as_ref()),
-            ("category", filter.category.as_ref()),
-            ("keyword", ""),
-            ("sort", "music"),
-            ("sort_type", "none"),
-        ])
-        .send()
-        .await?
-        .error_for_status()?;
-
-    Ok(FetchedPage {
-        page_num,
-        html_string: resp.text().await?,
-    })
-}
-
-fn parse_for_last_page(html_str: &str, selectors: &Selectors) -> anyhow::Result<usize> {
-    let html = Html::parse_document(html_str);
-    let mut max_page = 0;
+    const LIST_PAGE: &str = include_str!("../../fixtures/popnmusic/list.html");
 
-    for option in html.select(&selectors.page_select_options) {
-        let value: usize = option.value().attr("value").unwrap_or("0").parse()?;
-        if value > max_page {
-            max_page = value;
-        }
+    fn level(level: &str) -> Option<otoge::shared::level::Level> {
+        Some(level.parse().unwrap())
     }
 
-    Ok(max_page)
-}
-
-fn parse_songs_from_page(html_str: &str, selectors: &Selectors) -> anyhow::Result<Vec<ParsedSong>> {
-    let html = Html::parse_document(html_str);
-
-    let items: Vec<_> = html.select(&selectors.list_items).collect();
+    /// Expected values are what the extractor parsed from the fixture before the scrape spec
+    #[test]
+    fn parses_list_page_like_before() {
+        let parser = EntryParser::new(&SPEC.entry).unwrap();
+        let songs: Vec<ParsedSong> = parser
+            .parse_entries(0, LIST_PAGE)
+            .unwrap()
+            .into_iter()
+            .map(ParsedSong::from)
+            .collect();
 
-    items
-        .chunks(3)
-        .map(|chunk| {
-            anyhow::ensure!(
-                chunk.len() == 3,
-                "Expected 3 list items per song entry, got {}",
-                chunk.len()
-            );
+        let summary: Vec<(&str, &str, &str, &str)> = songs
+            .iter()
+            .map(|song| {
+                (
+                    song.image_id.as_str(),
+                    song.genre.as_str(),
+                    song.title.as_str(),
+                    song.artist.as_str(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                (
+                    "/game/popn/popn29/images/img_binary.html?img=AAAA",
+                    "*ハロー、プラネット。",
+                    "*ハロー、プラネット。",
+                    "sasakure.UK",
+                ),
+                (
+                    "/game/popn/popn29/images/img_binary.html?img=BBBB",
+                    "ポップ2",
+                    "Fixture Song (Long ver.)",
+                    "Fixture Artist",
+                ),
+            ]
+        );
+        assert_eq!(
+            songs[0].levels,
+            LevelMap {
+                light: level("9"),
+                normal: level("23"),
+                hyper: level("35"),
+                ex: level("42"),
+            }
+        );
+        assert_eq!(
+            songs[1].levels,
+            LevelMap {
+                light: level("4"),
+                normal: level("18"),
+                hyper: level("31"),
+                ex: None,
+            }
+        );
+    }
 
-            let image_id = chunk[0]
-                .select(&selectors.item_image)
-                .next()
-                .ok_or_else(|| anyhow::anyhow!("Song item missing img element"))?
-                .value()
-                .attr("src")
-                .ok_or_else(|| anyhow::anyhow!("Song img missing src attribute"))?
-                .to_owned();
-
-            let info_nodes: Vec<_> = chunk[1].select(&selectors.item_info).collect();
-            anyhow::ensure!(
-                info_nodes.len() == 3,
-                "Expected 3 info nodes, got {}",
-                info_nodes.len()
-            );
-            let genre = info_nodes[0].text().collect::<Vec<_>>().join("");
-            let title = info_nodes[1].text().collect::<Vec<_>>().join("");
-            let artist = info_nodes[2].text().collect::<Vec<_>>().join("");
-
-            let mut levels = LevelMap::default();
-            for level_p in chunk[2].select(&selectors.item_info) {
-                let level_value = level_p.value().attr("data-d").unwrap_or("-");
-                if level_value == "-" {
-                    continue;
-                }
+    #[test]
+    fn reads_pages_and_filters_from_list_page() {
+        let scraper = Scraper::new(&SPEC).unwrap();
+        let page = FetchedPage {
+            page_num: 0,
+            html_string: LIST_PAGE.to_owned(),
+        };
 
-                let level_name = level_p
-                    .select(&selectors.item_level_span)
-                    .next()
-                    .ok_or_else(|| anyhow::anyhow!("Level item missing span element"))?
-                    .text()
-                    .collect::<Vec<_>>()
-                    .join("");
-
-                let value = Some(level_value.to_owned());
-                match level_name.as_str() {
-                    "LIGHT" => levels.light = value,
-                    "NORMAL" => levels.normal = value,
-                    "HYPER" => levels.hyper = value,
-                    "EX" => levels.ex = value,
-                    _ => anyhow::bail!("Unknown level type '{level_name}'"),
-                }
-            }
+        assert_eq!(scraper.last_page(&page).unwrap(), 1);
 
-            Ok(ParsedSong {
-                image_id,
-                genre,
-                title,
-                artist,
-                levels,
-            })
-        })
-        .collect()
+        let options = scraper.filter_options(&page);
+        assert_eq!(options["version"], ["0", "1"]);
+        assert_eq!(options["bemani"], ["6"]);
+        assert_eq!(options["category"], ["1"]);
+        assert!(verify_filter_options(&options).is_ok());
+    }
 }
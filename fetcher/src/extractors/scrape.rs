@@ -0,0 +1,564 @@
+//! Generic scraping engine for paginated e-amusement song lists.
+//!
+//! A site is described by a static [`ScrapeSpec`], so a markup change on the site means
+//! editing the spec rather than the extraction code.
+
+use std::collections::HashMap;
+
+use anyhow::{Result, anyhow};
+use scraper::{ElementRef, Html, Selector};
+use tokio::task::JoinSet;
+use tracing::{Instrument, info};
+
+use crate::extractors::error::ExtractionError;
+use crate::http::HttpClient;
+use otoge::shared::level::Level;
+
+pub struct ScrapeSpec {
+    pub url: &'static str,
+    pub pagination: Pagination,
+    /// Parameters sent with every page request, in addition to the page number
+    pub params: &'static [(&'static str, &'static str)],
+    pub entry: EntrySpec,
+    /// Selects narrowing the song list down, e.g. by version
+    pub filters: &'static [FilterSpec],
+}
+
+pub struct Pagination {
+    pub method: PageMethod,
+    /// Name of the parameter holding the page number
+    pub page_param: &'static str,
+    pub first_page: usize,
+    /// Options listing every page, the highest value is the last page
+    pub page_options: &'static str,
+}
+
+/// A select of the site filtering the song list, sent as a request parameter
+pub struct FilterSpec {
+    /// Name of the parameter holding the selected option
+    pub param: &'static str,
+    /// Every option of the select
+    pub options: &'static str,
+    /// Option value listing every song
+    pub any: &'static str,
+}
+
+pub enum PageMethod {
+    /// Parameters are sent in the query string
+    Get,
+    /// Parameters are sent as a form body
+    Post,
+}
+
+pub struct EntrySpec {
+    pub selector: &'static str,
+    /// Number of consecutive matches making up a single song entry
+    pub group: usize,
+    pub fields: &'static [FieldSpec],
+    pub lists: &'static [ListSpec],
+    pub levels: Option<LevelSpec>,
+    pub counts: &'static [CountSpec],
+}
+
+/// Where a value is read from, relative to the selected element
+pub enum Value {
+    /// Text nodes joined with the separator
+    Text {
+        separator: &'static str,
+        trim: bool,
+    },
+    /// First text node only, leaving out text after nested markup like a `<br>`
+    FirstText {
+        trim: bool,
+    },
+    Attr(&'static str),
+    /// First class not in `ignore`
+    Class {
+        ignore: &'static [&'static str],
+    },
+    /// Value of the first descendant matching the selector
+    Child(&'static str, &'static Value),
+}
+
+/// Single value of an entry
+pub struct FieldSpec {
+    pub name: &'static str,
+    /// Index of the element within the entry group
+    pub part: usize,
+    pub selector: &'static str,
+    /// Which of the elements matching the selector to use
+    pub index: usize,
+    pub value: Value,
+}
+
+/// Exact number of elements matching the selector, so a markup change can't silently shift
+/// the elements fields are picked from by index
+pub struct CountSpec {
+    pub part: usize,
+    pub selector: &'static str,
+    pub count: usize,
+}
+
+/// `(id, label)` pairs from every element matching the selector, e.g. genres
+pub struct ListSpec {
+    pub name: &'static str,
+    pub part: usize,
+    pub selector: &'static str,
+    pub id: Value,
+    pub label: Value,
+}
+
+pub struct LevelSpec {
+    pub part: usize,
+    /// One element per chart
+    pub selector: &'static str,
+    pub difficulty: Value,
+    pub level: Value,
+    /// Site difficulty names mapped to the difficulty names of the data store
+    pub difficulties: &'static [(&'static str, &'static str)],
+    /// Charts with any of these classes don't exist
+    pub skip_classes: &'static [&'static str],
+    /// Level values meaning the chart doesn't exist
+    pub skip_values: &'static [&'static str],
+}
+
+pub struct ScrapedEntry {
+    fields: HashMap<&'static str, String>,
+    lists: HashMap<&'static str, Vec<(String, String)>>,
+    pub levels: Vec<(&'static str, Level)>,
+}
+
+impl ScrapedEntry {
+    /// Takes the value of a field of the spec, every field is present once extracted
+    pub fn take(&mut self, name: &str) -> String {
+        self.fields.remove(name).unwrap_or_default()
+    }
+
+    pub fn take_list(&mut self, name: &str) -> Vec<(String, String)> {
+        self.lists.remove(name).unwrap_or_default()
+    }
+}
+
+pub struct FetchedPage {
+    pub page_num: usize,
+    pub html_string: String,
+}
+
+pub struct Scraper {
+    spec: &'static ScrapeSpec,
+    parser: EntryParser,
+    page_options: Selector,
+    filter_options: Vec<Selector>,
+}
+
+impl Scraper {
+    pub fn new(spec: &'static ScrapeSpec) -> Result<Self> {
+        Ok(Self {
+            spec,
+            parser: EntryParser::new(&spec.entry)?,
+            page_options: parse_selector(spec.pagination.page_options)?,
+            filter_options: spec
+                .filters
+                .iter()
+                .map(|filter| parse_selector(filter.options))
+                .collect::<Result<_>>()?,
+        })
+    }
+
+    /// Request parameters of every filter, only `selected` narrows the list while the other
+    /// filters are set to their "any" option
+    pub fn filter_params(&self, selected: Option<(&str, &str)>) -> Vec<(&'static str, String)> {
+        self.spec
+            .filters
+            .iter()
+            .map(|filter| {
+                let value = match selected {
+                    Some((param, value)) if param == filter.param => value,
+                    _ => filter.any,
+                };
+                (filter.param, value.to_owned())
+            })
+            .collect()
+    }
+
+    /// Option values the page offers for each filter parameter, without the "any" option
+    pub fn filter_options(&self, page: &FetchedPage) -> HashMap<&'static str, Vec<String>> {
+        let html = Html::parse_document(&page.html_string);
+
+        self.spec
+            .filters
+            .iter()
+            .zip(&self.filter_options)
+            .map(|(filter, selector)| {
+                let values = html
+                    .select(selector)
+                    .filter_map(|option| option.value().attr("value"))
+                    .filter(|value| *value != filter.any)
+                    .map(str::to_owned)
+                    .collect();
+                (filter.param, values)
+            })
+            .collect()
+    }
+
+    pub fn first_page(&self) -> usize {
+        self.spec.pagination.first_page
+    }
+
+    /// Fetches and parses every page, using `params` as additional request parameters
+    pub async fn fetch_all(
+        &self,
+        client: &HttpClient,
+        params: &[(&'static str, String)],
+        prefetched_first_page: Option<FetchedPage>,
+    ) -> Result<Vec<ScrapedEntry>> {
+        let first_page = match prefetched_first_page {
+            Some(page) => page,
+            None => fetch_page(client, self.spec, self.first_page(), params).await?,
+        };
+
+        let last_page = self.last_page(&first_page)?;
+        info!("Got {} total pages", last_page + 1 - self.first_page());
+
+        let mut pages = vec![(first_page.page_num, self.parse_page(&first_page)?)];
+
+        let mut joinset = JoinSet::new();
+
+        for page_num in (self.first_page() + 1)..=last_page {
+            let client = client.clone();
+            let spec = self.spec;
+            let params = params.to_vec();
+
+            joinset.spawn(
+                async move { fetch_page(&client, spec, page_num, &params).await }
+                    .instrument(tracing::Span::current()),
+            );
+        }
+
+        while let Some(result) = joinset.join_next().await {
+            let fetched = result??;
+            pages.push((fetched.page_num, self.parse_page(&fetched)?));
+        }
+
+        pages.sort_by_key(|(page_num, _)| *page_num);
+
+        Ok(pages.into_iter().flat_map(|(_, entries)| entries).collect())
+    }
+
+    pub async fn fetch_page(
+        &self,
+        client: &HttpClient,
+        page_num: usize,
+        params: &[(&'static str, String)],
+    ) -> Result<FetchedPage> {
+        fetch_page(client, self.spec, page_num, params).await
+    }
+
+    fn parse_page(&self, page: &FetchedPage) -> Result<Vec<ScrapedEntry>, ExtractionError> {
+        info!("Parsing page {}", page.page_num);
+        self.parser.parse_entries(page.page_num, &page.html_string)
+    }
+
+    pub fn last_page(&self, page: &FetchedPage) -> Result<usize, ExtractionError> {
+        let html = Html::parse_document(&page.html_string);
+        let source = self.spec.pagination.page_options;
+        let mut last_page = self.first_page();
+
+        for option in html.select(&self.page_options) {
+            let value = option.value().attr("value").unwrap_or("0");
+            let option_page_num: usize = value.parse().map_err(|_| {
+                ExtractionError::new(
+                    page.page_num,
+                    None,
+                    source,
+                    option,
+                    format!("Invalid page number '{value}'"),
+                )
+            })?;
+            last_page = last_page.max(option_page_num);
+        }
+
+        Ok(last_page)
+    }
+}
+
+/// Extracts song entries from a page according to an [`EntrySpec`]
+pub struct EntryParser {
+    spec: &'static EntrySpec,
+    selectors: HashMap<&'static str, Selector>,
+}
+
+impl EntryParser {
+    pub fn new(spec: &'static EntrySpec) -> Result<Self> {
+        let mut sources = vec![spec.selector];
+        if let Some(levels) = &spec.levels {
+            sources.push(levels.selector);
+            collect_child_selectors(&levels.difficulty, &mut sources);
+            collect_child_selectors(&levels.level, &mut sources);
+        }
+        for field in spec.fields {
+            sources.push(field.selector);
+            collect_child_selectors(&field.value, &mut sources);
+        }
+        for list in spec.lists {
+            sources.push(list.selector);
+            collect_child_selectors(&list.id, &mut sources);
+            collect_child_selectors(&list.label, &mut sources);
+        }
+        sources.extend(spec.counts.iter().map(|count| count.selector));
+
+        let selectors = sources
+            .into_iter()
+            .map(|source| parse_selector(source).map(|selector| (source, selector)))
+            .collect::<Result<_>>()?;
+
+        Ok(Self { spec, selectors })
+    }
+
+    pub fn parse_entries(
+        &self,
+        page_num: usize,
+        html_string: &str,
+    ) -> Result<Vec<ScrapedEntry>, ExtractionError> {
+        let html = Html::parse_document(html_string);
+        let source = self.spec.selector;
+        let elements: Vec<_> = html.select(self.selector(source)).collect();
+
+        elements
+            .chunks(self.spec.group)
+            .enumerate()
+            .map(|(entry_index, parts)| {
+                if parts.len() != self.spec.group {
+                    return Err(ExtractionError::new(
+                        page_num,
+                        Some(entry_index),
+                        source,
+                        parts[0],
+                        format!(
+                            "Expected {} elements per song entry, got {}",
+                            self.spec.group,
+                            parts.len()
+                        ),
+                    ));
+                }
+
+                let entry = EntryContext {
+                    page_num,
+                    entry_index,
+                    parts,
+                };
+                self.parse_entry(&entry)
+            })
+            .collect()
+    }
+
+    fn parse_entry(&self, entry: &EntryContext<'_, '_>) -> Result<ScrapedEntry, ExtractionError> {
+        for count in self.spec.counts {
+            let found = entry
+                .part(count.part)
+                .select(self.selector(count.selector))
+                .count();
+            if found != count.count {
+                return Err(entry.error(
+                    count.part,
+                    count.selector,
+                    format!("Expected {} elements, got {}", count.count, found),
+                ));
+            }
+        }
+
+        let mut fields = HashMap::new();
+        for field in self.spec.fields {
+            let element = entry
+                .part(field.part)
+                .select(self.selector(field.selector))
+                .nth(field.index)
+                .ok_or_else(|| {
+                    entry.error(
+                        field.part,
+                        field.selector,
+                        format!("Missing element for field '{}'", field.name),
+                    )
+                })?;
+
+            let value = self.value(element, &field.value).ok_or_else(|| {
+                entry.error(
+                    field.part,
+                    field.selector,
+                    format!("Missing value for field '{}'", field.name),
+                )
+            })?;
+            fields.insert(field.name, value);
+        }
+
+        let mut lists = HashMap::new();
+        for list in self.spec.lists {
+            let items = entry
+                .part(list.part)
+                .select(self.selector(list.selector))
+                .map(|element| {
+                    let id = self.value(element, &list.id);
+                    let label = self.value(element, &list.label);
+                    id.zip(label).ok_or_else(|| {
+                        entry.error(
+                            list.part,
+                            list.selector,
+                            format!("Missing value in list '{}'", list.name),
+                        )
+                    })
+                })
+                .collect::<Result<_, _>>()?;
+            lists.insert(list.name, items);
+        }
+
+        let mut levels = vec![];
+        let level_elements = self.spec.levels.iter().flat_map(|spec| {
+            entry
+                .part(spec.part)
+                .select(self.selector(spec.selector))
+                .map(move |element| (spec, element))
+        });
+        for (spec, element) in level_elements {
+            if element
+                .value()
+                .classes()
+                .any(|class| spec.skip_classes.contains(&class))
+            {
+                continue;
+            }
+
+            // A chart without a level doesn't exist
+            let Some(value) = self.value(element, &spec.level) else {
+                continue;
+            };
+            if spec.skip_values.contains(&value.as_str()) {
+                continue;
+            }
+
+            let site_difficulty = self
+                .value(element, &spec.difficulty)
+                .ok_or_else(|| entry.error(spec.part, spec.selector, "Missing level difficulty"))?;
+            let difficulty = spec
+                .difficulties
+                .iter()
+                .find(|(site, _)| *site == site_difficulty)
+                .map(|(_, difficulty)| *difficulty)
+                .ok_or_else(|| {
+                    entry.error(
+                        spec.part,
+                        spec.selector,
+                        format!("Unknown level type '{site_difficulty}'"),
+                    )
+                })?;
+
+            let level = value.parse::<Level>().map_err(|err| {
+                entry.error(
+                    spec.part,
+                    spec.selector,
+                    format!("Failed to parse {difficulty} level: {err}"),
+                )
+            })?;
+            levels.push((difficulty, level));
+        }
+
+        Ok(ScrapedEntry {
+            fields,
+            lists,
+            levels,
+        })
+    }
+
+    fn value(&self, element: ElementRef<'_>, value: &Value) -> Option<String> {
+        match value {
+            Value::Text { separator, trim } => {
+                let text = element.text().collect::<Vec<_>>().join(separator);
+                Some(if *trim { text.trim().to_owned() } else { text })
+            }
+            Value::FirstText { trim } => element
+                .text()
+                .next()
+                .map(|text| if *trim { text.trim() } else { text }.to_owned()),
+            Value::Attr(name) => element.value().attr(name).map(str::to_owned),
+            Value::Class { ignore } => element
+                .value()
+                .classes()
+                .find(|class| !ignore.contains(class))
+                .map(str::to_owned),
+            Value::Child(selector, value) => element
+                .select(self.selector(selector))
+                .next()
+                .and_then(|child| self.value(child, value)),
+        }
+    }
+
+    fn selector(&self, source: &str) -> &Selector {
+        // Every selector of the spec is compiled in `new`
+        &self.selectors[source]
+    }
+}
+
+/// Song entry being parsed, used to locate extraction errors
+struct EntryContext<'a, 'b> {
+    page_num: usize,
+    entry_index: usize,
+    parts: &'b [ElementRef<'a>],
+}
+
+impl<'a> EntryContext<'a, '_> {
+    fn part(&self, index: usize) -> ElementRef<'a> {
+        self.parts[index]
+    }
+
+    fn error(
+        &self,
+        part: usize,
+        selector: &'static str,
+        message: impl Into<String>,
+    ) -> ExtractionError {
+        ExtractionError::new(
+            self.page_num,
+            Some(self.entry_index),
+            selector,
+            self.part(part),
+            message,
+        )
+    }
+}
+
+fn parse_selector(source: &'static str) -> Result<Selector> {
+    Selector::parse(source).map_err(|e| anyhow!("Failed to parse CSS selector `{source}`: {e}"))
+}
+
+fn collect_child_selectors(value: &'static Value, sources: &mut Vec<&'static str>) {
+    if let Value::Child(selector, value) = value {
+        sources.push(selector);
+        collect_child_selectors(value, sources);
+    }
+}
+
+async fn fetch_page(
+    client: &HttpClient,
+    spec: &ScrapeSpec,
+    page_num: usize,
+    params: &[(&'static str, String)],
+) -> Result<FetchedPage> {
+    let pagination = &spec.pagination;
+    info!("Fetching page {}", page_num + 1 - pagination.first_page);
+
+    let page = page_num.to_string();
+    let mut all_params: Vec<(&str, &str)> = vec![(pagination.page_param, page.as_str())];
+    all_params.extend(params.iter().map(|(name, value)| (*name, value.as_str())));
+    all_params.extend(spec.params.iter().copied());
+
+    let request = match pagination.method {
+        PageMethod::Get => client.get(spec.url).query(&all_params),
+        PageMethod::Post => client.post(spec.url).form(&all_params),
+    };
+
+    let resp = request.send().await?.error_for_status()?;
+
+    Ok(FetchedPage {
+        page_num,
+        html_string: resp.text(),
+    })
+}
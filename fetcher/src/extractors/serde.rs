@@ -3,6 +3,7 @@ use async_trait::async_trait;
 use otoge::shared::traits::Otoge;
 use serde::de::DeserializeOwned;
 
+use crate::http::HttpClient;
 use crate::traits::{Extractor, FetchTask};
 
 pub struct SerdeGetExtractor;
@@ -14,9 +15,9 @@ where
     G::Song: std::convert::From<G::ApiSong>,
     G::ApiSong: DeserializeOwned,
 {
-    async fn fetch_songs(client: &reqwest::Client) -> anyhow::Result<Vec<G::Song>> {
-        let resp = client.get(G::api_url()).send().await?;
-        let data = resp.json::<Vec<G::ApiSong>>().await?;
+    async fn fetch_songs(client: &HttpClient) -> anyhow::Result<Vec<G::Song>> {
+        let resp = client.get(G::api_url()).send().await?.error_for_status()?;
+        let data = resp.json::<Vec<G::ApiSong>>()?;
 
         Ok(data.into_iter().map(|song| song.into()).collect())
     }
@@ -1,14 +1,142 @@
-use std::{borrow::Cow, sync::Arc};
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::sync::Arc;
 
+use anyhow::{Context, bail};
 use async_trait::async_trait;
-use scraper::{Html, Selector, error::SelectorErrorKind};
-use tokio::{sync::Semaphore, task::JoinSet};
-use tracing::{Instrument, info, info_span};
+use reqwest::Url;
+use tokio::task::JoinSet;
+use tracing::{Instrument, info, warn};
 
+use crate::extractors::scrape::{
+    EntryParser, EntrySpec, FieldSpec, LevelSpec, ListSpec, PageMethod, Pagination, ScrapeSpec,
+    ScrapedEntry, Scraper, Value,
+};
+use crate::http::HttpClient;
 use crate::traits::{Extractor, FetchTask};
 use otoge::{
     shared::traits::Otoge,
-    soundvoltex::models::{Category, LevelMap, Song},
+    soundvoltex::models::{Category, ChartDetails, Song},
+};
+
+const TEXT: Value = Value::Text {
+    separator: " ",
+    trim: false,
+};
+
+/// Site chart classes mapped to the difficulties of [`LevelMap`](otoge::soundvoltex::models::LevelMap)
+const DIFFICULTIES: &[(&str, &str)] = &[
+    ("nov", "novice"),
+    ("adv", "advanced"),
+    ("exh", "exhaust"),
+    ("mxm", "maximum"),
+    ("inf", "infinite"),
+    ("grv", "gravity"),
+    ("hvn", "heavenly"),
+    ("vvd", "vivid"),
+    ("xcd", "exceed"),
+    ("ult", "ultimate"),
+];
+
+static SPEC: ScrapeSpec = ScrapeSpec {
+    url: "https://p.eagate.573.jp/game/sdvx/vi/music/index.html",
+    pagination: Pagination {
+        method: PageMethod::Post,
+        page_param: "page",
+        first_page: 1,
+        page_options: r#"#music-result > select#search_page > option"#,
+    },
+    params: &[],
+    entry: EntrySpec {
+        selector: r#"#music-result > .music"#,
+        group: 1,
+        fields: &[
+            FieldSpec {
+                name: "title",
+                part: 0,
+                selector: r#".cat > .inner > .info > p"#,
+                index: 0,
+                value: TEXT,
+            },
+            FieldSpec {
+                name: "artist",
+                part: 0,
+                selector: r#".cat > .inner > .info > p"#,
+                index: 1,
+                value: TEXT,
+            },
+            FieldSpec {
+                name: "image_id",
+                part: 0,
+                selector: r#".cat > .jk > a[href] > img"#,
+                index: 0,
+                value: Value::Attr("src"),
+            },
+            // The music_id in the link changes regularly, so it's only used to reach the
+            // detail page during the current fetch
+            FieldSpec {
+                name: "detail_url",
+                part: 0,
+                selector: r#".cat > .jk > a[href]"#,
+                index: 0,
+                value: Value::Attr("href"),
+            },
+        ],
+        lists: &[ListSpec {
+            name: "genres",
+            part: 0,
+            selector: r#".genre"#,
+            id: Value::Class { ignore: &["genre"] },
+            label: Value::FirstText { trim: false },
+        }],
+        levels: Some(LevelSpec {
+            part: 0,
+            selector: r#".cat > .inner > .level > p"#,
+            difficulty: Value::Class { ignore: &[] },
+            level: Value::FirstText { trim: true },
+            difficulties: DIFFICULTIES,
+            skip_classes: &["none"],
+            skip_values: &[],
+        }),
+        counts: &[],
+    },
+    filters: &[],
+};
+
+const CREDIT: Value = Value::Text {
+    separator: "",
+    trim: true,
+};
+
+/// Song detail page, with one `.chart` element per chart
+static DETAIL_SPEC: EntrySpec = EntrySpec {
+    selector: r#"#music-detail"#,
+    group: 1,
+    fields: &[FieldSpec {
+        name: "bpm",
+        part: 0,
+        selector: r#".info .bpm"#,
+        index: 0,
+        value: CREDIT,
+    }],
+    lists: &[
+        ListSpec {
+            name: "effectors",
+            part: 0,
+            selector: r#".chart"#,
+            id: Value::Class { ignore: &["chart"] },
+            label: Value::Child(r#".effector"#, &CREDIT),
+        },
+        ListSpec {
+            name: "illustrators",
+            part: 0,
+            selector: r#".chart"#,
+            id: Value::Class { ignore: &["chart"] },
+            label: Value::Child(r#".illustrator"#, &CREDIT),
+        },
+    ],
+    levels: None,
+    counts: &[],
 };
 
 pub struct SoundVoltexVIIExtractor;
@@ -20,237 +148,351 @@ where
     G::Song: Send,
     Vec<<G as Otoge>::Song>: FromIterator<Song>,
 {
-    async fn fetch_songs(client: &reqwest::Client) -> anyhow::Result<Vec<G::Song>> {
-        let selectors = Selectors::init().unwrap();
-        let first_page = 1;
-        let name = G::name();
-
-        let first_page_content = get_page_content(client, first_page).await?;
-        let page_count = parse_page_count(first_page_content.html_string.as_str(), &selectors)?;
-        info!("Got {} total pages", page_count);
-
-        let mut pages = vec![process_page::<G>(
-            first_page,
-            first_page_content.html_string,
-            &selectors,
-        )?];
-
-        let semaphore = Arc::new(Semaphore::new(10));
-        let mut joinset = JoinSet::new();
-
-        for page_num in (first_page + 1)..=page_count {
-            let sem_local = Arc::clone(&semaphore);
-            let client = client.clone();
-
-            joinset.spawn(async move {
-                let _permit = sem_local.acquire_owned().await.unwrap();
-
-                get_page_content(&client, page_num)
-                    .instrument(info_span!("fetch_remote", name))
-                    .await
-            });
-        }
+    async fn fetch_songs(client: &HttpClient) -> anyhow::Result<Vec<G::Song>> {
+        let scraper = Scraper::new(&SPEC)?;
+        let entries = scraper.fetch_all(client, &[], None).await?;
 
-        while let Some(join_result) = joinset.join_next().await {
-            let fetch_result = join_result?;
-            let fetched_page = fetch_result?;
-            pages.push(process_page::<G>(
-                fetched_page.page_num,
-                fetched_page.html_string,
-                &selectors,
-            )?);
-        }
-
-        let mut extracted = vec![];
-
-        pages.sort_by_key(|p| p.page_num);
-        for page in pages {
-            extracted.extend(page.songs);
-        }
-
-        Ok(extracted)
+        Ok(entries.into_iter().map(song_from_entry).collect())
     }
 }
 
-#[derive(Debug)]
-struct ExtractedPage<G>
-where
-    G: Otoge,
-{
-    page_num: usize,
-    songs: Vec<G::Song>,
-}
+fn song_from_entry(mut entry: ScrapedEntry) -> Song {
+    let detail_url = entry.take("detail_url");
+    let categories = entry
+        .take_list("genres")
+        .into_iter()
+        .map(|(id, name)| Category {
+            id: Cow::Owned(id),
+            name: Cow::Owned(name),
+        })
+        .collect();
 
-pub struct FetchedPage {
-    page_num: usize,
-    html_string: String,
+    Song {
+        // Assigned from the id registry once all songs are fetched
+        id: String::new(),
+        image_id: entry.take("image_id"),
+        title: entry.take("title"),
+        artist: entry.take("artist"),
+        // Tracked across fetches from the local song list
+        first_seen: None,
+        removed_at: None,
+        categories,
+        levels: entry.levels.into_iter().collect(),
+        // Filled in by `enrich_songs`
+        bpm: None,
+        charts: vec![],
+        detail_url: Some(detail_url),
+    }
 }
 
-struct Selectors {
-    music_entry: Selector,
-    page_select_options: Selector,
-
-    //item_link: Selector,
-    item_image: Selector,
-    item_genre: Selector,
-    item_info: Selector,
-    item_level: Selector,
-}
+/// Adds BPM and chart credits from the detail pages.
+///
+/// Details are copied from the local song with the same id as long as its levels didn't change,
+/// so only new and changed songs are fetched. Without `fetch`, no detail page is fetched and
+/// local details are kept as they are. A detail page that can't be fetched or parsed is logged
+/// and leaves the local details of the song in place.
+pub async fn enrich_songs(
+    client: &HttpClient,
+    local_songs: &[Song],
+    songs: &mut [Song],
+    fetch: bool,
+) -> anyhow::Result<()> {
+    let cached: HashMap<&str, &Song> = local_songs
+        .iter()
+        .filter(|song| song.has_details())
+        .map(|song| (song.id.as_str(), song))
+        .collect();
 
-impl Selectors {
-    fn init<'a>() -> Result<Self, SelectorErrorKind<'a>> {
-        Ok(Self {
-            music_entry: Selector::parse(r#"#music-result > .music"#)?,
-            page_select_options: Selector::parse(r#"#music-result > select#search_page > option"#)?,
-
-            //item_link: Selector::parse(r#".cat > .jk > a[href]"#)?,
-            item_image: Selector::parse(r#".cat > .jk > a[href] > img"#)?,
-            item_genre: Selector::parse(r#".genre"#)?,
-            item_info: Selector::parse(r#".cat > .inner > .info > p"#)?,
-            item_level: Selector::parse(r#".cat > .inner > .level > p"#)?,
-        })
+    let mut missing = vec![];
+    for (index, song) in songs.iter_mut().enumerate() {
+        match cached.get(song.id.as_str()) {
+            Some(cached) if !fetch || cached.levels == song.levels => {
+                song.bpm.clone_from(&cached.bpm);
+                song.charts.clone_from(&cached.charts);
+            }
+            Some(cached) => {
+                // Kept in case the detail page can't be fetched
+                song.bpm.clone_from(&cached.bpm);
+                song.charts.clone_from(&cached.charts);
+                missing.push(index);
+            }
+            None => missing.push(index),
+        }
     }
-}
 
-async fn get_page_content(
-    client: &reqwest::Client,
-    page_num: usize,
-) -> anyhow::Result<FetchedPage> {
-    info!("Fetching song index, page {}", page_num);
-    let url = "https://p.eagate.573.jp/game/sdvx/vi/music/index.html";
-
-    let resp = client
-        .post(url)
-        .form(&[("page", page_num.to_string().as_str())])
-        .send()
-        .await?;
-
-    Ok(FetchedPage {
-        page_num,
-        html_string: resp.text().await?,
-    })
-}
+    if !fetch || missing.is_empty() {
+        return Ok(());
+    }
 
-fn process_page<G>(
-    page_num: usize,
-    html_string: String,
-    selectors: &Selectors,
-) -> anyhow::Result<ExtractedPage<G>>
-where
-    G: Otoge,
-    Vec<<G as Otoge>::Song>: FromIterator<Song>,
-{
-    info!("Parsing page {}", page_num);
-    let html = Html::parse_document(html_string.as_str());
-
-    let songs = html
-        .select(&selectors.music_entry)
-        .map(|music_entry| {
-            let genres = music_entry
-                .select(&selectors.item_genre)
-                .map(|genre_node| {
-                    let genre_id = genre_node
-                        .value()
-                        .attr("class")
-                        .unwrap()
-                        .split(' ')
-                        .find(|s| *s != "genre")
-                        .unwrap();
-
-                    let genre = genre_node.text().next().unwrap();
-
-                    Category {
-                        id: Cow::Owned(genre_id.to_owned()),
-                        name: Cow::Owned(genre.to_owned()),
-                    }
-                })
-                .collect();
-
-            let mut levels = LevelMap::default();
-            for level in music_entry.select(&selectors.item_level) {
-                let level_id = level.value().attr("class").unwrap().trim();
-                if level_id.ends_with(" none") {
-                    continue;
+    info!("Fetching {} song detail pages", missing.len());
+
+    let parser = Arc::new(EntryParser::new(&DETAIL_SPEC)?);
+    let base_url = Url::parse(SPEC.url)?;
+    let mut joinset = JoinSet::new();
+
+    // Detail pages are numbered in fetch order, the url is attached to errors
+    for (page_num, index) in missing.into_iter().enumerate() {
+        let Some(detail_url) = &songs[index].detail_url else {
+            continue;
+        };
+
+        let url = base_url.join(detail_url)?;
+        let client = client.clone();
+        let parser = Arc::clone(&parser);
+
+        joinset.spawn(
+            async move {
+                let details = async {
+                    let resp = client.get(url.as_str()).send().await?.error_for_status()?;
+
+                    let mut entries = parser
+                        .parse_entries(page_num, &resp.text())
+                        .with_context(|| format!("Failed to parse song details at {url}"))?;
+                    entries
+                        .pop()
+                        .ok_or_else(|| anyhow::anyhow!("No song details found at {url}"))
                 }
+                .await;
 
-                let level_value = level.text().next().unwrap().trim().to_owned();
-                match level_id {
-                    "nov" => levels.novice = Some(level_value),
-                    "adv" => levels.advanced = Some(level_value),
-                    "exh" => levels.exhaust = Some(level_value),
-                    "mxm" => levels.maximum = Some(level_value),
-                    "inf" => levels.infinite = Some(level_value),
-                    "grv" => levels.gravity = Some(level_value),
-                    "hvn" => levels.heavenly = Some(level_value),
-                    "vvd" => levels.vivid = Some(level_value),
-                    "xcd" => levels.exceed = Some(level_value),
-                    "ult" => levels.ultimate = Some(level_value),
-                    _ => panic!("Unknown level type '{level_id}' on page {page_num}"),
-                }
+                (index, details)
             }
+            .instrument(tracing::Span::current()),
+        );
+    }
 
-            let mut info_nodes = music_entry.select(&selectors.item_info);
-            let title = info_nodes
-                .next()
-                .unwrap()
-                .text()
-                .collect::<Vec<&str>>()
-                .join(" ");
-            let artist = info_nodes
-                .next()
-                .unwrap()
-                .text()
-                .collect::<Vec<&str>>()
-                .join(" ");
-
-            // FIXME: id changes regularly
-            //let id = music_entry
-            //    .select(&selectors.item_link)
-            //    .next()
-            //    .unwrap()
-            //    .value()
-            //    .attr("href")
-            //    .unwrap()
-            //    .to_owned()
-            //    .replacen("/game/sdvx/vi/music/detail.html?music_id=", "", 1);
-            //assert!(!id.contains('/'));
-
-            let image_id = music_entry
-                .select(&selectors.item_image)
-                .next()
-                .unwrap()
-                .value()
-                .attr("src")
-                .unwrap()
-                .to_owned();
-
-            Song {
-                image_id,
-                title,
-                artist,
-                categories: genres,
-                levels,
-            }
+    while let Some(result) = joinset.join_next().await {
+        let (index, details) = result?;
+        let applied = details.and_then(|entry| apply_details(&mut songs[index], entry));
+        if let Err(err) = applied {
+            warn!(
+                "Keeping local details of {} [{}]: {:#}",
+                songs[index].title, songs[index].id, err
+            );
+        }
+    }
+
+    Ok(())
+}
+
+/// Fails without touching the song when the page has no chart credits, which means the chart
+/// markup no longer matches `DETAIL_SPEC` since every chart credits its effector
+fn apply_details(song: &mut Song, mut entry: ScrapedEntry) -> anyhow::Result<()> {
+    let credits = |pairs: Vec<(String, String)>| -> HashMap<String, String> {
+        pairs
+            .into_iter()
+            .filter(|(_, credit)| !credit.is_empty())
+            .collect()
+    };
+    let mut effectors = credits(entry.take_list("effectors"));
+    let mut illustrators = credits(entry.take_list("illustrators"));
+
+    let bpm = Some(entry.take("bpm")).filter(|bpm| !bpm.is_empty());
+    let charts: Vec<ChartDetails> = DIFFICULTIES
+        .iter()
+        .filter_map(|(class, difficulty)| {
+            let effector = effectors.remove(*class);
+            let illustrator = illustrators.remove(*class);
+            (effector.is_some() || illustrator.is_some()).then(|| ChartDetails {
+                difficulty: (*difficulty).to_owned(),
+                effector,
+                illustrator,
+            })
         })
         .collect();
 
-    Ok(ExtractedPage::<G> { page_num, songs })
+    if charts.is_empty() {
+        bail!("No chart credits found");
+    }
+
+    song.bpm = bpm;
+    song.charts = charts;
+    Ok(())
 }
 
-fn parse_page_count(html_str: &str, selectors: &Selectors) -> anyhow::Result<usize> {
-    let html = Html::parse_document(html_str);
-    let mut max_page = 0;
-
-    for option_element in html.select(&selectors.page_select_options) {
-        let page_num: usize = option_element
-            .value()
-            .attr("value")
-            .unwrap_or("0")
-            .parse()?;
-        if page_num > max_page {
-            max_page = page_num;
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::extractors::scrape::FetchedPage;
+
+    const DETAIL_PAGE: &str = include_str!("../../fixtures/soundvoltex_vii/detail.html");
+
+    fn song() -> Song {
+        Song {
+            id: "1".to_owned(),
+            image_id: "/jk.png".to_owned(),
+            title: "Fixture Song".to_owned(),
+            artist: "Fixture Artist".to_owned(),
+            bpm: None,
+            first_seen: None,
+            removed_at: None,
+            categories: vec![],
+            levels: Default::default(),
+            charts: vec![],
+            detail_url: None,
         }
     }
 
-    Ok(max_page)
+    #[test]
+    fn parses_detail_page() {
+        let parser = EntryParser::new(&DETAIL_SPEC).unwrap();
+        let mut entries = parser.parse_entries(0, DETAIL_PAGE).unwrap();
+        assert_eq!(entries.len(), 1);
+
+        let mut song = song();
+        apply_details(&mut song, entries.pop().unwrap()).unwrap();
+
+        assert_eq!(song.bpm.as_deref(), Some("100-200"));
+        assert_eq!(
+            song.charts,
+            [
+                ("novice", Some("Effector A"), Some("Illustrator A")),
+                ("advanced", Some("Effector A"), Some("Illustrator A")),
+                ("exhaust", Some("Effector B"), None),
+                ("maximum", Some("Effector C"), Some("Illustrator C")),
+            ]
+            .map(|(difficulty, effector, illustrator)| ChartDetails {
+                difficulty: difficulty.to_owned(),
+                effector: effector.map(str::to_owned),
+                illustrator: illustrator.map(str::to_owned),
+            })
+        );
+    }
+
+    #[test]
+    fn finds_no_details_on_other_pages() {
+        let parser = EntryParser::new(&DETAIL_SPEC).unwrap();
+        let entries = parser
+            .parse_entries(0, "<html><body><p>Maintenance</p></body></html>")
+            .unwrap();
+
+        assert!(entries.is_empty());
+    }
+
+    #[test]
+    fn keeps_details_when_the_page_markup_does_not_match() {
+        let parser = EntryParser::new(&DETAIL_SPEC).unwrap();
+        let entry = parser
+            .parse_entries(
+                0,
+                r#"<div id="music-detail"><div class="info"><p class="bpm">200</p></div></div>"#,
+            )
+            .unwrap()
+            .pop()
+            .unwrap();
+
+        let mut song = song();
+        song.bpm = Some("180".to_owned());
+        assert!(apply_details(&mut song, entry).is_err());
+        assert_eq!(song.bpm.as_deref(), Some("180"));
+    }
+
+    #[test]
+    fn reads_genre_labels_from_the_first_text_node() {
+        let page = r#"<div id="music-result"><div class="music"><div class="cat">
+            <div class="genre vocaloid">ボーカロイド<br><span>VOCALOID</span></div>
+            <div class="genre toho"> 東方アレンジ </div>
+            <div class="jk"><a href="/detail"><img src="/jk.png"></a></div>
+            <div class="inner"><div class="info"><p>Title</p><p>Artist</p></div>
+            <div class="level"><p class="nov"> 5 <span>NOV</span></p></div></div>
+        </div></div></div>"#;
+        let parser = EntryParser::new(&SPEC.entry).unwrap();
+        let song = song_from_entry(parser.parse_entries(1, page).unwrap().pop().unwrap());
+
+        let genres: Vec<(&str, &str)> = song
+            .categories
+            .iter()
+            .map(|category| (category.id.as_ref(), category.name.as_ref()))
+            .collect();
+        assert_eq!(
+            genres,
+            [("vocaloid", "ボーカロイド"), ("toho", " 東方アレンジ ")]
+        );
+        assert_eq!(song.levels.charts(), [("novice", "5".parse().unwrap())]);
+    }
+
+    const LIST_PAGE: &str = include_str!("../../fixtures/soundvoltex_vii/list.html");
+
+    /// Expected values are what the extractor parsed from the fixture before the scrape spec
+    #[test]
+    fn parses_list_page_like_before() {
+        let scraper = Scraper::new(&SPEC).unwrap();
+        let page = FetchedPage {
+            page_num: 1,
+            html_string: LIST_PAGE.to_owned(),
+        };
+        assert_eq!(scraper.last_page(&page).unwrap(), 3);
+
+        let parser = EntryParser::new(&SPEC.entry).unwrap();
+        let songs: Vec<Song> = parser
+            .parse_entries(1, LIST_PAGE)
+            .unwrap()
+            .into_iter()
+            .map(song_from_entry)
+            .collect();
+
+        let summary: Vec<_> = songs
+            .iter()
+            .map(|song| {
+                (
+                    song.image_id.as_str(),
+                    song.title.as_str(),
+                    song.artist.as_str(),
+                    song.categories
+                        .iter()
+                        .map(|category| (category.id.as_ref(), category.name.as_ref()))
+                        .collect(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                (
+                    "/game/sdvx/vi/common/jacket.html?img=AAAA",
+                    "Fixture Song",
+                    "Fixture Artist feat. Singer",
+                    vec![("pops", "POPS&ANIME"), ("vocaloid", "ボーカロイド")],
+                ),
+                (
+                    "/game/sdvx/vi/common/jacket.html?img=BBBB",
+                    "Second Song",
+                    "Second Artist",
+                    vec![("toho", "東方アレンジ")],
+                ),
+            ]
+        );
+
+        let levels = |song: &Song| -> Vec<(&str, String)> {
+            song.levels
+                .charts()
+                .into_iter()
+                .map(|(difficulty, level)| (difficulty, level.to_string()))
+                .collect()
+        };
+        assert_eq!(
+            levels(&songs[0]),
+            [
+                ("novice", "5"),
+                ("advanced", "11"),
+                ("exhaust", "16"),
+                ("vivid", "18"),
+            ]
+            .map(|(difficulty, level)| (difficulty, level.to_owned()))
+        );
+        assert_eq!(
+            levels(&songs[1]),
+            [
+                ("novice", "3"),
+                ("advanced", "9"),
+                ("exhaust", "14"),
+                ("maximum", "17"),
+                ("ultimate", "20"),
+            ]
+            .map(|(difficulty, level)| (difficulty, level.to_owned()))
+        );
+        assert_eq!(
+            songs[0].detail_url.as_deref(),
+            Some("/game/sdvx/vi/music/detail.html?music_id=AAAA")
+        );
+    }
 }
@@ -0,0 +1,657 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use anyhow::{Context, Result, bail};
+use base64::Engine;
+use base64::prelude::BASE64_STANDARD;
+use encoding_rs::{Encoding, UTF_8};
+use reqwest::header::CONTENT_TYPE;
+use reqwest::{Method, StatusCode};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use tokio::sync::Semaphore;
+use tokio::time::Instant;
+use tracing::{debug, warn};
+
+#[derive(Debug)]
+pub enum HttpMode {
+    Live,
+    /// Performs requests and saves every request/response pair into the cassette directory
+    Record(PathBuf),
+    /// Serves responses from the cassette directory without touching the network
+    Replay(PathBuf),
+}
+
+#[derive(Debug, Clone)]
+pub struct HttpOptions {
+    /// Number of times a failed request is retried before giving up
+    pub max_retries: u32,
+    /// Delay before the first retry, doubled on every following attempt
+    pub initial_backoff: Duration,
+    /// Maximum number of requests started per second against a single host
+    pub requests_per_second: f64,
+    /// Maximum number of requests in flight at once, across every host
+    pub max_concurrent_requests: usize,
+    pub timeout: Duration,
+}
+
+impl Default for HttpOptions {
+    fn default() -> Self {
+        Self {
+            max_retries: 4,
+            initial_backoff: Duration::from_millis(500),
+            requests_per_second: 5.0,
+            max_concurrent_requests: 8,
+            timeout: Duration::from_secs(30),
+        }
+    }
+}
+
+/// HTTP client shared by every extractor.
+///
+/// Live requests are rate limited per host, limited to [`HttpOptions::max_concurrent_requests`]
+/// in flight, time out after [`HttpOptions::timeout`] and are retried with exponential backoff on
+/// connection errors, 429 and 5xx responses. Callers can spawn every request at once.
+#[derive(Clone)]
+pub struct HttpClient {
+    client: reqwest::Client,
+    mode: Arc<HttpMode>,
+    options: Arc<HttpOptions>,
+    rate_limiter: Arc<RateLimiter>,
+    in_flight: Arc<Semaphore>,
+}
+
+impl HttpClient {
+    pub fn new(client: reqwest::Client, mode: HttpMode, options: HttpOptions) -> Self {
+        let rate_limiter = RateLimiter::new(options.requests_per_second);
+        let in_flight = Semaphore::new(options.max_concurrent_requests.max(1));
+
+        Self {
+            client,
+            mode: Arc::new(mode),
+            options: Arc::new(options),
+            rate_limiter: Arc::new(rate_limiter),
+            in_flight: Arc::new(in_flight),
+        }
+    }
+
+    pub fn get(&self, url: &str) -> RequestBuilder<'_> {
+        self.request(Method::GET, url)
+    }
+
+    pub fn post(&self, url: &str) -> RequestBuilder<'_> {
+        self.request(Method::POST, url)
+    }
+
+    fn request(&self, method: Method, url: &str) -> RequestBuilder<'_> {
+        RequestBuilder {
+            client: self,
+            inner: self
+                .client
+                .request(method, url)
+                .timeout(self.options.timeout),
+        }
+    }
+
+    async fn execute(&self, request: reqwest::Request) -> Result<Response> {
+        match self.mode.as_ref() {
+            HttpMode::Live => self.execute_live(request).await,
+            HttpMode::Record(cassette_dir) => {
+                let cassette = Cassette::for_request(&request);
+                let response = self.execute_live(request).await?;
+
+                let recorded = cassette.with_response(&response);
+                recorded.save(cassette_dir).await?;
+
+                Ok(response)
+            }
+            HttpMode::Replay(cassette_dir) => {
+                let cassette = Cassette::for_request(&request);
+                let recorded = cassette.load(cassette_dir).await?;
+
+                Ok(Response {
+                    url: request.url().to_string(),
+                    status: StatusCode::from_u16(recorded.status)?,
+                    body: recorded.body()?,
+                    charset: recorded.charset,
+                })
+            }
+        }
+    }
+
+    async fn execute_live(&self, request: reqwest::Request) -> Result<Response> {
+        let mut attempt = 0;
+
+        loop {
+            // Bodies are always buffered (query strings and forms), so this only fails for
+            // streaming bodies, which are sent once without retrying
+            let Some(retry_request) = request.try_clone() else {
+                return self.execute_once(request).await;
+            };
+
+            let result = self.execute_once(retry_request).await;
+            if !is_retryable(&result) || attempt >= self.options.max_retries {
+                return result;
+            }
+
+            let delay = self.backoff(attempt);
+            match &result {
+                Ok(response) => warn!(
+                    "HTTP status {} for {}, retrying in {:?}",
+                    response.status, response.url, delay
+                ),
+                Err(err) => warn!(
+                    "Request to {} failed: {}, retrying in {:?}",
+                    request.url(),
+                    err,
+                    delay
+                ),
+            }
+
+            tokio::time::sleep(delay).await;
+            attempt += 1;
+        }
+    }
+
+    async fn execute_once(&self, request: reqwest::Request) -> Result<Response> {
+        let url = request.url().to_string();
+        // Held until the body is read, but not while backing off between attempts
+        let _permit = self.in_flight.acquire().await?;
+        self.rate_limiter
+            .wait(request.url().host_str().unwrap_or_default())
+            .await;
+
+        let resp = self.client.execute(request).await?;
+        let status = resp.status();
+        let charset = resp
+            .headers()
+            .get(CONTENT_TYPE)
+            .and_then(|content_type| content_type.to_str().ok())
+            .and_then(charset);
+        let body = resp.bytes().await?.to_vec();
+
+        Ok(Response {
+            url,
+            status,
+            charset,
+            body,
+        })
+    }
+
+    /// Exponential backoff with jitter, so concurrent retries don't hit the host all at once
+    fn backoff(&self, attempt: u32) -> Duration {
+        let base = self.options.initial_backoff * 2u32.saturating_pow(attempt);
+        base.mul_f64(0.5 + fastrand::f64() * 0.5)
+    }
+}
+
+/// Connection errors, timeouts, 429 and 5xx responses are worth another attempt
+fn is_retryable(result: &Result<Response>) -> bool {
+    match result {
+        Ok(response) => {
+            response.status == StatusCode::TOO_MANY_REQUESTS || response.status.is_server_error()
+        }
+        Err(_) => true,
+    }
+}
+
+/// Charset parameter of a `Content-Type` header, e.g. `Shift_JIS` for `text/html; charset=Shift_JIS`
+fn charset(content_type: &str) -> Option<String> {
+    content_type.split(';').skip(1).find_map(|parameter| {
+        let (name, value) = parameter.split_once('=')?;
+        name.trim()
+            .eq_ignore_ascii_case("charset")
+            .then(|| value.trim().trim_matches('"').to_owned())
+    })
+}
+
+/// Spaces out the start of requests to the same host
+struct RateLimiter {
+    interval: Duration,
+    next_slot: Mutex<HashMap<String, Instant>>,
+}
+
+impl RateLimiter {
+    fn new(requests_per_second: f64) -> Self {
+        let interval = if requests_per_second > 0.0 {
+            Duration::from_secs_f64(1.0 / requests_per_second)
+        } else {
+            Duration::ZERO
+        };
+
+        Self {
+            interval,
+            next_slot: Mutex::new(HashMap::new()),
+        }
+    }
+
+    async fn wait(&self, host: &str) {
+        let slot = self.reserve(host, Instant::now());
+        tokio::time::sleep_until(slot).await;
+    }
+
+    /// Takes the next free slot of the host, no earlier than `now`
+    fn reserve(&self, host: &str, now: Instant) -> Instant {
+        let mut next_slot = self.next_slot.lock().expect("rate limiter lock poisoned");
+        let next = next_slot.entry(host.to_owned()).or_insert(now);
+
+        let slot = (*next).max(now);
+        *next = slot + self.interval;
+        slot
+    }
+}
+
+pub struct RequestBuilder<'a> {
+    client: &'a HttpClient,
+    inner: reqwest::RequestBuilder,
+}
+
+impl RequestBuilder<'_> {
+    pub fn query<T>(self, query: &T) -> Self
+    where
+        T: Serialize + ?Sized,
+    {
+        Self {
+            inner: self.inner.query(query),
+            ..self
+        }
+    }
+
+    pub fn form<T>(self, form: &T) -> Self
+    where
+        T: Serialize + ?Sized,
+    {
+        Self {
+            inner: self.inner.form(form),
+            ..self
+        }
+    }
+
+    pub async fn send(self) -> Result<Response> {
+        let request = self.inner.build()?;
+        self.client.execute(request).await
+    }
+}
+
+pub struct Response {
+    url: String,
+    status: StatusCode,
+    /// Charset of the `Content-Type` header, bodies are only decoded when read as text
+    charset: Option<String>,
+    body: Vec<u8>,
+}
+
+impl Response {
+    pub fn error_for_status(self) -> Result<Self> {
+        if self.status.is_client_error() || self.status.is_server_error() {
+            bail!("HTTP status {} for url ({})", self.status, self.url);
+        }
+
+        Ok(self)
+    }
+
+    pub fn status(&self) -> StatusCode {
+        self.status
+    }
+
+    /// Decodes the body with the charset of the response, UTF-8 if it didn't declare one
+    pub fn text(self) -> String {
+        let encoding = self
+            .charset
+            .as_deref()
+            .and_then(|charset| Encoding::for_label(charset.as_bytes()))
+            .unwrap_or(UTF_8);
+
+        encoding.decode(&self.body).0.into_owned()
+    }
+
+    pub fn bytes(self) -> Vec<u8> {
+        self.body
+    }
+
+    pub fn json<T>(&self) -> Result<T>
+    where
+        T: DeserializeOwned,
+    {
+        serde_json::from_slice(&self.body)
+            .with_context(|| format!("Failed to decode JSON response from {}", self.url))
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct Cassette {
+    method: String,
+    url: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    request_body: Option<String>,
+    status: u16,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    charset: Option<String>,
+    body: String,
+    /// Set instead of `body` for responses that aren't valid UTF-8, e.g. images
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    body_base64: Option<String>,
+}
+
+impl Cassette {
+    fn for_request(request: &reqwest::Request) -> Self {
+        Self {
+            method: request.method().to_string(),
+            url: request.url().to_string(),
+            request_body: request
+                .body()
+                .and_then(|body| body.as_bytes())
+                .map(|bytes| String::from_utf8_lossy(bytes).into_owned()),
+            status: 0,
+            charset: None,
+            body: String::new(),
+            body_base64: None,
+        }
+    }
+
+    fn with_response(self, response: &Response) -> Self {
+        let (body, body_base64) = match String::from_utf8(response.body.clone()) {
+            Ok(body) => (body, None),
+            Err(_) => (String::new(), Some(BASE64_STANDARD.encode(&response.body))),
+        };
+
+        Self {
+            status: response.status.as_u16(),
+            charset: response.charset.clone(),
+            body,
+            body_base64,
+            ..self
+        }
+    }
+
+    fn body(&self) -> Result<Vec<u8>> {
+        match &self.body_base64 {
+            Some(encoded) => Ok(BASE64_STANDARD.decode(encoded)?),
+            None => Ok(self.body.clone().into_bytes()),
+        }
+    }
+
+    /// File name derived from the request, so the same request always maps to the same cassette
+    fn file_name(&self) -> String {
+        let mut hash = Fnv1a::default();
+        hash.write(self.method.as_bytes());
+        hash.write(b"\0");
+        hash.write(self.url.as_bytes());
+        hash.write(b"\0");
+        hash.write(self.request_body.as_deref().unwrap_or("").as_bytes());
+
+        format!("{:016x}.json", hash.0)
+    }
+
+    async fn save(&self, cassette_dir: &Path) -> Result<()> {
+        tokio::fs::create_dir_all(cassette_dir).await?;
+
+        let path = cassette_dir.join(self.file_name());
+        debug!("Recording {} {} to {:?}", self.method, self.url, path);
+
+        tokio::fs::write(&path, serde_json::to_string_pretty(self)?).await?;
+        Ok(())
+    }
+
+    async fn load(&self, cassette_dir: &Path) -> Result<Self> {
+        let path = cassette_dir.join(self.file_name());
+        debug!("Replaying {} {} from {:?}", self.method, self.url, path);
+
+        let contents = tokio::fs::read_to_string(&path).await.with_context(|| {
+            format!(
+                "No recorded response for {} {} (expected at {:?})",
+                self.method, self.url, path
+            )
+        })?;
+
+        Ok(serde_json::from_str(&contents)?)
+    }
+}
+
+/// 64-bit FNV-1a, stable across Rust releases unlike `DefaultHasher`
+pub struct Fnv1a(u64);
+
+impl Default for Fnv1a {
+    fn default() -> Self {
+        Self(0xcbf29ce484222325)
+    }
+}
+
+impl Fnv1a {
+    pub fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 ^= u64::from(*byte);
+            self.0 = self.0.wrapping_mul(0x100000001b3);
+        }
+    }
+
+    pub fn finish(&self) -> u64 {
+        self.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_server::{TestResponse, TestServer};
+
+    use super::*;
+
+    const PNG: &[u8] = b"\x89PNG\r\n\x1a\n\x00\x00\x00\rIHDR\xff\xfe";
+
+    fn client(mode: HttpMode) -> HttpClient {
+        let options = HttpOptions {
+            max_retries: 0,
+            requests_per_second: 0.0,
+            ..HttpOptions::default()
+        };
+        HttpClient::new(reqwest::Client::new(), mode, options)
+    }
+
+    async fn start_server() -> TestServer {
+        TestServer::start(
+            |request| match (request.method.as_str(), request.path.as_str()) {
+                ("GET", "/page") => TestResponse::new(200, "text/html", "<p>ポップン</p>"),
+                // "ポップン" in Shift_JIS
+                ("GET", "/sjis") => TestResponse::new(
+                    200,
+                    "text/html; charset=Shift_JIS",
+                    b"\x83|\x83b\x83v\x83\x93".as_slice(),
+                ),
+                ("GET", "/jacket") => TestResponse::new(200, "", PNG),
+                ("POST", "/search") => {
+                    TestResponse::new(200, "application/json", request.body.clone())
+                }
+                _ => TestResponse::new(404, "text/plain", "Not Found"),
+            },
+        )
+        .await
+    }
+
+    async fn fetch_all(client: &HttpClient, server: &TestServer) -> Result<Vec<(u16, Vec<u8>)>> {
+        let mut responses = vec![];
+        for path in ["/page", "/jacket", "/missing"] {
+            let response = client.get(&server.url(path)).send().await?;
+            responses.push((response.status().as_u16(), response.bytes()));
+        }
+        for query in ["first", "second"] {
+            let response = client
+                .post(&server.url("/search"))
+                .form(&[("q", query)])
+                .send()
+                .await?;
+            responses.push((response.status().as_u16(), response.bytes()));
+        }
+        Ok(responses)
+    }
+
+    #[tokio::test]
+    async fn keeps_bodies_without_content_type_as_is() -> Result<()> {
+        let server = start_server().await;
+        let client = client(HttpMode::Live);
+
+        let jacket = client.get(&server.url("/jacket")).send().await?;
+        assert_eq!(jacket.bytes(), PNG);
+
+        let page = client.get(&server.url("/sjis")).send().await?;
+        assert_eq!(page.text(), "ポップン");
+
+        assert_eq!(server.requests().len(), 2);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn replays_recorded_responses() -> Result<()> {
+        let cassette_dir =
+            std::env::temp_dir().join(format!("otoge-cassettes-{}", std::process::id()));
+        let _ = tokio::fs::remove_dir_all(&cassette_dir).await;
+
+        let server = start_server().await;
+        let recorded = fetch_all(&client(HttpMode::Record(cassette_dir.clone())), &server).await?;
+        let sjis = client(HttpMode::Record(cassette_dir.clone()))
+            .get(&server.url("/sjis"))
+            .send()
+            .await?;
+        assert_eq!(sjis.text(), "ポップン");
+        assert_eq!(server.requests().len(), 6);
+
+        let replay = client(HttpMode::Replay(cassette_dir.clone()));
+        let replayed = fetch_all(&replay, &server).await?;
+        let sjis = replay.get(&server.url("/sjis")).send().await?;
+        let unrecorded = replay.get(&server.url("/other")).send().await;
+        tokio::fs::remove_dir_all(&cassette_dir).await?;
+
+        // Replaying never reaches the server
+        assert_eq!(server.requests().len(), 6);
+        assert!(unrecorded.is_err());
+        assert_eq!(sjis.text(), "ポップン");
+
+        assert_eq!(replayed, recorded);
+        assert_eq!(
+            replayed,
+            [
+                (200, "<p>ポップン</p>".as_bytes().to_vec()),
+                (200, PNG.to_vec()),
+                (404, b"Not Found".to_vec()),
+                (200, b"q=first".to_vec()),
+                (200, b"q=second".to_vec()),
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn spaces_out_slots_per_host() {
+        let rate_limiter = RateLimiter::new(4.0);
+        let now = Instant::now();
+        let interval = Duration::from_millis(250);
+
+        assert_eq!(rate_limiter.reserve("a", now), now);
+        assert_eq!(rate_limiter.reserve("a", now), now + interval);
+        assert_eq!(rate_limiter.reserve("a", now), now + interval * 2);
+        // Other hosts have their own slots
+        assert_eq!(rate_limiter.reserve("b", now), now);
+
+        // A host that has been idle starts right away
+        let later = now + Duration::from_secs(10);
+        assert_eq!(rate_limiter.reserve("a", later), later);
+        assert_eq!(rate_limiter.reserve("a", later), later + interval);
+    }
+
+    #[test]
+    fn unlimited_rate_never_waits() {
+        let rate_limiter = RateLimiter::new(0.0);
+        let now = Instant::now();
+
+        assert_eq!(rate_limiter.reserve("a", now), now);
+        assert_eq!(rate_limiter.reserve("a", now), now);
+    }
+
+    #[test]
+    fn retries_rate_limits_and_server_errors() {
+        let response = |status: u16| {
+            Ok(Response {
+                url: "http://localhost/".to_owned(),
+                status: StatusCode::from_u16(status).unwrap(),
+                charset: None,
+                body: vec![],
+            })
+        };
+
+        assert!(is_retryable(&response(429)));
+        assert!(is_retryable(&response(500)));
+        assert!(is_retryable(&response(503)));
+        assert!(is_retryable(&Err(anyhow::anyhow!("connection reset"))));
+        assert!(!is_retryable(&response(200)));
+        assert!(!is_retryable(&response(404)));
+        assert!(!is_retryable(&response(403)));
+    }
+
+    #[test]
+    fn doubles_backoff_with_jitter() {
+        let client = HttpClient::new(
+            reqwest::Client::new(),
+            HttpMode::Live,
+            HttpOptions {
+                initial_backoff: Duration::from_millis(100),
+                ..HttpOptions::default()
+            },
+        );
+
+        for attempt in 0..4 {
+            let base = Duration::from_millis(100) * 2u32.pow(attempt);
+            let delays: Vec<Duration> = (0..100).map(|_| client.backoff(attempt)).collect();
+
+            assert!(
+                delays
+                    .iter()
+                    .all(|delay| *delay >= base / 2 && *delay <= base)
+            );
+            // Jittered, so concurrent retries are spread out
+            assert!(delays.iter().any(|delay| *delay != delays[0]));
+        }
+    }
+
+    #[tokio::test]
+    async fn retries_until_the_server_recovers() -> Result<()> {
+        let failures = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let server = TestServer::start({
+            let failures = failures.clone();
+            move |request| match request.path.as_str() {
+                "/flaky" if failures.fetch_add(1, std::sync::atomic::Ordering::SeqCst) < 2 => {
+                    TestResponse::new(503, "text/plain", "Unavailable")
+                }
+                "/flaky" => TestResponse::new(200, "text/plain", "OK"),
+                "/busy" => TestResponse::new(429, "text/plain", "Too Many Requests"),
+                _ => TestResponse::new(404, "text/plain", "Not Found"),
+            }
+        })
+        .await;
+        let client = HttpClient::new(
+            reqwest::Client::new(),
+            HttpMode::Live,
+            HttpOptions {
+                max_retries: 2,
+                initial_backoff: Duration::from_millis(1),
+                requests_per_second: 0.0,
+                ..HttpOptions::default()
+            },
+        );
+
+        let flaky = client.get(&server.url("/flaky")).send().await?;
+        assert_eq!(flaky.status(), StatusCode::OK);
+        assert_eq!(server.requests().len(), 3);
+
+        // Client errors aren't retried
+        let missing = client.get(&server.url("/missing")).send().await?;
+        assert_eq!(missing.status(), StatusCode::NOT_FOUND);
+        assert_eq!(server.requests().len(), 4);
+
+        // The last response is returned once the retries are used up
+        let busy = client.get(&server.url("/busy")).send().await?;
+        assert_eq!(busy.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(server.requests().len(), 7);
+        Ok(())
+    }
+}
@@ -0,0 +1,276 @@
+use std::path::Path;
+use std::sync::Arc;
+
+use anyhow::{Context, Result};
+use reqwest::{StatusCode, Url};
+use tokio::task::JoinSet;
+use tracing::{Instrument, info, warn};
+
+use otoge::helpers::load_local_data_store;
+use otoge::shared::jackets::{JacketEntry, JacketManifest};
+use otoge::shared::traits::{DataStore as DataStoreTrait, Otoge, Song as SongTrait};
+
+use crate::http::{Fnv1a, HttpClient};
+
+enum Download {
+    Saved(JacketEntry),
+    Missing(String),
+}
+
+/// Downloads the jacket of every available song of the local data store into
+/// `<game>/jackets/`, skipping songs whose jacket is already mirrored.
+///
+/// `asset_origin` replaces the scheme and host of the jacket urls, e.g. to mirror from a local
+/// copy of the asset host.
+pub async fn mirror<G>(
+    client: &HttpClient,
+    data_path: &Path,
+    asset_origin: Option<&Url>,
+) -> Result<()>
+where
+    G: Otoge,
+    G::Song: SongTrait,
+    G::DataStore: DataStoreTrait<Song = G::Song> + serde::de::DeserializeOwned,
+{
+    if G::jacket_base_url().is_none() {
+        info!("No jackets available, skipping");
+        return Ok(());
+    }
+
+    let Some(data_store) = load_local_data_store::<G>(Some(data_path)).await? else {
+        return Ok(());
+    };
+
+    let jackets_path = G::jackets_path(Some(data_path));
+    tokio::fs::create_dir_all(&jackets_path).await?;
+
+    let manifest_path = G::jacket_manifest_path(Some(data_path));
+    let mut manifest = JacketManifest::load(&manifest_path).await?;
+    manifest.missing.clear();
+
+    let jackets_path = Arc::new(jackets_path);
+    let mut joinset = JoinSet::new();
+    let mut present = 0;
+
+    for song in data_store.available_songs() {
+        let Some(image_id) = song.image_id().filter(|image_id| !image_id.is_empty()) else {
+            continue;
+        };
+
+        let key = song.key().into_owned();
+        let is_present = match manifest.jackets.get(&key) {
+            Some(entry) if entry.image_id == image_id => {
+                tokio::fs::try_exists(jackets_path.join(&entry.file)).await?
+            }
+            _ => false,
+        };
+        if is_present {
+            present += 1;
+            continue;
+        }
+
+        let Some(url) = G::jacket_url(image_id) else {
+            continue;
+        };
+        let url = with_origin(&url, asset_origin)?;
+
+        let client = client.clone();
+        let jackets_path = Arc::clone(&jackets_path);
+        let image_id = image_id.to_owned();
+        let title = song.title().to_owned();
+
+        joinset.spawn(
+            async move {
+                let download = download(&client, &url, &image_id, &jackets_path)
+                    .await
+                    .with_context(|| format!("Failed to download jacket of {title} at {url}"))?;
+
+                if let Download::Missing(_) = download {
+                    warn!("Missing jacket: {} [{}] at {}", title, key, url);
+                }
+
+                Ok::<_, anyhow::Error>((key, download))
+            }
+            .instrument(tracing::Span::current()),
+        );
+    }
+
+    let mut downloaded = 0;
+    while let Some(result) = joinset.join_next().await {
+        let (key, download) = result??;
+        match download {
+            Download::Saved(entry) => {
+                downloaded += 1;
+                manifest.jackets.insert(key, entry);
+            }
+            Download::Missing(url) => {
+                manifest.missing.insert(key, url);
+            }
+        }
+    }
+
+    info!(
+        "Jackets: {} downloaded, {} already present, {} missing",
+        downloaded,
+        present,
+        manifest.missing.len()
+    );
+
+    manifest.save(&manifest_path).await?;
+
+    Ok(())
+}
+
+async fn download(
+    client: &HttpClient,
+    url: &str,
+    image_id: &str,
+    jackets_path: &Path,
+) -> Result<Download> {
+    let resp = client.get(url).send().await?;
+    if resp.status() == StatusCode::NOT_FOUND {
+        return Ok(Download::Missing(url.to_owned()));
+    }
+
+    let bytes = resp.error_for_status()?.bytes();
+    if bytes.is_empty() {
+        return Ok(Download::Missing(url.to_owned()));
+    }
+
+    let mut hash = Fnv1a::default();
+    hash.write(&bytes);
+    let file = format!("{:016x}.{}", hash.finish(), extension(&bytes));
+
+    // Identical jackets are shared between songs, the file only has to be written once
+    let path = jackets_path.join(&file);
+    if !tokio::fs::try_exists(&path).await? {
+        tokio::fs::write(&path, &bytes).await?;
+    }
+
+    Ok(Download::Saved(JacketEntry {
+        image_id: image_id.to_owned(),
+        file,
+    }))
+}
+
+/// Sniffs the image format, some asset hosts serve jackets from urls without an extension
+fn extension(bytes: &[u8]) -> &'static str {
+    match bytes {
+        [0x89, b'P', b'N', b'G', ..] => "png",
+        [0xFF, 0xD8, 0xFF, ..] => "jpg",
+        [b'G', b'I', b'F', b'8', ..] => "gif",
+        bytes if bytes.starts_with(b"RIFF") && bytes.get(8..12) == Some(b"WEBP") => "webp",
+        _ => "bin",
+    }
+}
+
+fn with_origin(url: &str, origin: Option<&Url>) -> Result<String> {
+    let Some(origin) = origin else {
+        return Ok(url.to_owned());
+    };
+
+    let url = Url::parse(url)?;
+    let mut rewritten = origin.join(url.path())?;
+    rewritten.set_query(url.query());
+
+    Ok(rewritten.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use otoge::chunithm::ChunithmJP;
+
+    use crate::http::{HttpMode, HttpOptions};
+    use crate::test_server::{TestResponse, TestServer};
+
+    use super::*;
+
+    const PNG: &[u8] = b"\x89PNG\r\n\x1a\n\x00\x00\x00\rIHDR";
+
+    const DATA_STORE: &str = r#"
+name = "chunithm_jp"
+count = 2
+last_updated = "2026-08-20T03:38:19Z"
+categories = []
+
+[[songs]]
+id = "1"
+title = "Jacket"
+title_reading = "JACKET"
+artist = "Artist"
+image_id = "present.jpg"
+category = "ORIGINAL"
+is_new = false
+
+[[songs]]
+id = "2"
+title = "No Jacket"
+title_reading = "NOJACKET"
+artist = "Artist"
+image_id = "missing.jpg"
+category = "ORIGINAL"
+is_new = false
+"#;
+
+    #[tokio::test]
+    async fn mirrors_jackets_from_the_asset_origin() -> Result<()> {
+        let data_path = std::env::temp_dir().join(format!("otoge-jackets-{}", std::process::id()));
+        let _ = tokio::fs::remove_dir_all(&data_path).await;
+        tokio::fs::create_dir_all(ChunithmJP::data_path(Some(&data_path))).await?;
+        tokio::fs::write(
+            ChunithmJP::music_data_store_path(Some(&data_path)),
+            DATA_STORE,
+        )
+        .await?;
+
+        let server = TestServer::start(|request| match request.path.as_str() {
+            "/chuni-mobile/html/mobile/img/present.jpg" => TestResponse::new(200, "", PNG),
+            _ => TestResponse::new(404, "text/html", "Not Found"),
+        })
+        .await;
+        let origin = Url::parse(&server.url("/"))?;
+        let options = HttpOptions {
+            max_retries: 0,
+            requests_per_second: 0.0,
+            ..HttpOptions::default()
+        };
+        let client = HttpClient::new(reqwest::Client::new(), HttpMode::Live, options);
+
+        mirror::<ChunithmJP>(&client, &data_path, Some(&origin)).await?;
+
+        let mut hash = Fnv1a::default();
+        hash.write(PNG);
+        let file = format!("{:016x}.png", hash.finish());
+        let jackets_path = ChunithmJP::jackets_path(Some(&data_path));
+        let manifest_path = ChunithmJP::jacket_manifest_path(Some(&data_path));
+
+        assert_eq!(tokio::fs::read(jackets_path.join(&file)).await?, PNG);
+        let manifest = JacketManifest::load(&manifest_path).await?;
+        assert_eq!(
+            manifest.jackets.get("1"),
+            Some(&JacketEntry {
+                image_id: "present.jpg".to_owned(),
+                file: file.clone(),
+            })
+        );
+        assert_eq!(manifest.jackets.get("2"), None);
+        assert_eq!(
+            manifest.missing.get("2"),
+            Some(&server.url("/chuni-mobile/html/mobile/img/missing.jpg"))
+        );
+        assert_eq!(server.requests().len(), 2);
+
+        // The mirrored jacket is skipped, only the missing one is tried again
+        mirror::<ChunithmJP>(&client, &data_path, Some(&origin)).await?;
+        let paths: Vec<String> = server
+            .requests()
+            .into_iter()
+            .map(|request| request.path)
+            .collect();
+        tokio::fs::remove_dir_all(&data_path).await?;
+
+        assert_eq!(paths.len(), 3);
+        assert_eq!(paths[2], "/chuni-mobile/html/mobile/img/missing.jpg");
+        Ok(())
+    }
+}
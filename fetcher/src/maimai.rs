@@ -5,15 +5,17 @@ use async_trait::async_trait;
 
 use otoge::maimai::models::{DataStore, Song, SongFromAPI};
 use otoge::maimai::{MaimaiIntl, MaimaiJP, get_all_intl_categories, get_all_jp_categories};
-use otoge::shared::traits::Otoge;
+use otoge::shared::traits::{DataStore as DataStoreTrait, Otoge};
 
 use crate::FetchTask;
 use crate::extractors::serde::SerdeGetExtractor;
+use crate::http::HttpClient;
+use crate::safeguard::Safeguard;
 
 trait Maimai {
     fn impl_verify_categories(data_store: &DataStore) -> Result<()> {
         let categories = &data_store.categories;
-        let songs = &data_store.songs;
+        let songs = data_store.available_songs();
 
         let all_categories: HashSet<&str> = categories.iter().map(|c| c.name.as_ref()).collect();
 
@@ -34,6 +36,22 @@ trait Maimai {
 
         Ok(())
     }
+
+    fn impl_verify_versions(data_store: &DataStore) -> Result<()> {
+        let unknown_versions: HashSet<&str> = data_store
+            .available_songs()
+            .filter(|song| data_store.version_of(song).is_none())
+            .map(Song::version)
+            .collect();
+
+        ensure!(
+            unknown_versions.is_empty(),
+            "Local version definitions lack {:?}",
+            unknown_versions
+        );
+
+        Ok(())
+    }
 }
 
 impl Maimai for MaimaiJP {}
@@ -46,11 +64,19 @@ impl FetchTask<Self> for MaimaiJP {
         "https://maimai.sega.jp/data/maimai_songs.json"
     }
 
+    fn safeguard() -> Safeguard {
+        Safeguard {
+            min_count: 700,
+            ..Safeguard::default()
+        }
+    }
+
     async fn verify_categories(
-        _client: &reqwest::Client,
+        _client: &HttpClient,
         data_store: &<Self as Otoge>::DataStore,
     ) -> Result<()> {
-        Self::impl_verify_categories(data_store)
+        Self::impl_verify_categories(data_store)?;
+        Self::impl_verify_versions(data_store)
     }
 
     fn new_data_store(songs: Vec<<Self as Otoge>::Song>) -> <Self as Otoge>::DataStore {
@@ -68,11 +94,19 @@ impl FetchTask<Self> for MaimaiIntl {
         "https://maimai.sega.com/assets/data/maimai_songs.json"
     }
 
+    fn safeguard() -> Safeguard {
+        Safeguard {
+            min_count: 700,
+            ..Safeguard::default()
+        }
+    }
+
     async fn verify_categories(
-        _client: &reqwest::Client,
+        _client: &HttpClient,
         data_store: &<Self as Otoge>::DataStore,
     ) -> Result<()> {
-        Self::impl_verify_categories(data_store)
+        Self::impl_verify_categories(data_store)?;
+        Self::impl_verify_versions(data_store)
     }
 
     fn new_data_store(songs: Vec<Song>) -> DataStore {
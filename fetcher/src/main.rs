@@ -1,6 +1,13 @@
 mod extractors;
+mod http;
+mod jackets;
+mod report;
+mod safeguard;
 mod traits;
 
+#[cfg(test)]
+mod test_server;
+
 mod chunithm;
 mod maimai;
 mod ongeki;
@@ -8,20 +15,28 @@ mod polarischord;
 mod popnmusic;
 mod soundvoltex;
 
-use std::path::Path;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
 
+use http::{HttpClient, HttpMode, HttpOptions};
 use otoge::helpers::load_local_data_store;
+use report::ChangeReport;
+use safeguard::SafeguardOverrides;
 use traits::{Extractor, FetchTask};
 
-use otoge::chunithm::{ChunithmIntl, ChunithmJP};
-use otoge::maimai::{MaimaiIntl, MaimaiJP};
-use otoge::ongeki::Ongeki;
-use otoge::polarischord::PolarisChord;
-use otoge::popnmusic::PopNMusic;
-use otoge::shared::traits::{DataStore as DataStoreTrait, Otoge};
-use otoge::soundvoltex::SoundVoltex;
+use otoge::for_each_game;
+use otoge::shared::availability::track_availability;
+use otoge::shared::changelog::{Changelog, ChangelogEntry};
+use otoge::shared::diff::DataStoreDiff;
+use otoge::shared::ids::IdRegistry;
+use otoge::shared::registry::all_games;
+use otoge::shared::traits::{DataStore as DataStoreTrait, Otoge, Song as SongTrait};
 
-use anyhow::{Error, Result};
+use anyhow::{Error, Result, bail};
+use chrono::Utc;
+use clap::Parser;
+use reqwest::Url;
 use tokio::task::JoinSet;
 use tracing::metadata::LevelFilter;
 use tracing::{Instrument, error, info, info_span, warn};
@@ -30,15 +45,110 @@ use tracing_subscriber::EnvFilter;
 const DATA_PATH: &str = "./data";
 const DEFAULT_USER_AGENT: &str = include_str!("./default_user_agent.txt");
 
+#[derive(Parser, Debug)]
+#[command(
+    version,
+    about = "Fetch music game song lists into the local data stores"
+)]
+struct Args {
+    /// Games to fetch, fetches every game if none are given
+    #[arg(value_name = "GAME")]
+    games: Vec<String>,
+
+    /// Directory containing the data stores
+    #[arg(long, default_value = DATA_PATH)]
+    data_dir: PathBuf,
+
+    /// Fetch, verify and show the differences without writing anything
+    #[arg(long)]
+    dry_run: bool,
+
+    /// Exit with an error if the remote data differs from the local data, implies --dry-run
+    #[arg(long)]
+    check: bool,
+
+    /// Write new data even if it removes more songs than the per-game safeguard allows
+    #[arg(long)]
+    allow_mass_removal: bool,
+
+    #[command(flatten)]
+    safeguard: SafeguardOverrides,
+
+    /// Follow song detail pages for data missing from the song lists, e.g. SOUND VOLTEX BPMs
+    #[arg(long)]
+    fetch_details: bool,
+
+    /// Mirror the jacket of every song into <GAME>/jackets/ after fetching
+    #[arg(long)]
+    jackets: bool,
+
+    /// Only mirror jackets of the local data stores, without fetching the song lists
+    #[arg(long, conflicts_with = "jackets")]
+    jackets_only: bool,
+
+    /// Download jackets from this origin instead of the game's asset host, keeping the path
+    #[arg(long, value_name = "URL")]
+    asset_origin: Option<Url>,
+
+    /// Write a Markdown report of the changes to FILE, or to stdout if FILE is "-"
+    #[arg(long, value_name = "FILE")]
+    report: Option<PathBuf>,
+
+    /// Save every HTTP request and response into DIR while fetching
+    #[arg(long, value_name = "DIR", conflicts_with = "replay")]
+    record: Option<PathBuf>,
+
+    /// Serve HTTP responses from a directory written by --record instead of the network
+    #[arg(long, value_name = "DIR")]
+    replay: Option<PathBuf>,
+
+    /// Maximum number of requests per second sent to a single host
+    #[arg(long, default_value_t = HttpOptions::default().requests_per_second)]
+    requests_per_second: f64,
+
+    /// Maximum number of requests in flight at once, across every host
+    #[arg(long, default_value_t = HttpOptions::default().max_concurrent_requests)]
+    max_concurrent_requests: usize,
+
+    /// Number of times a failed request is retried
+    #[arg(long, default_value_t = HttpOptions::default().max_retries)]
+    retries: u32,
+
+    /// Timeout of a single request, in seconds
+    #[arg(long, default_value_t = HttpOptions::default().timeout.as_secs())]
+    timeout: u64,
+}
+
+impl Args {
+    fn wants(&self, name: &str) -> bool {
+        self.games.is_empty() || self.games.iter().any(|game| game == name)
+    }
+}
+
+struct FetchOptions {
+    data_dir: PathBuf,
+    dry_run: bool,
+    allow_mass_removal: bool,
+    safeguard: SafeguardOverrides,
+    fetch_details: bool,
+    jackets: bool,
+    jackets_only: bool,
+    asset_origin: Option<Url>,
+}
+
 #[tokio::main]
 async fn main() -> Result<()> {
+    let args = Args::parse();
+
     let format = tracing_subscriber::fmt::format().with_target(false);
     let filter = EnvFilter::builder()
         .with_default_directive(LevelFilter::INFO.into())
         .from_env_lossy();
 
+    // Logs go to stderr, so a report written to stdout stays clean
     tracing_subscriber::fmt()
         .event_format(format)
+        .with_writer(std::io::stderr)
         .with_env_filter(filter)
         .init();
 
@@ -48,61 +158,145 @@ async fn main() -> Result<()> {
         env!("CARGO_PKG_VERSION")
     );
 
+    let all_games: Vec<&'static str> = all_games().iter().map(|game| game.name).collect();
+
+    if let Some(unknown) = args.games.iter().find(|g| !all_games.contains(&g.as_str())) {
+        bail!(
+            "Unknown game '{}', expected one of: {}",
+            unknown,
+            all_games.join(", ")
+        );
+    }
+
+    let options = Arc::new(FetchOptions {
+        data_dir: args.data_dir.clone(),
+        dry_run: args.dry_run || args.check,
+        allow_mass_removal: args.allow_mass_removal,
+        safeguard: args.safeguard,
+        fetch_details: args.fetch_details,
+        jackets: args.jackets,
+        jackets_only: args.jackets_only,
+        asset_origin: args.asset_origin.clone(),
+    });
+
+    let mode = match (args.record.clone(), args.replay.clone()) {
+        (Some(dir), _) => HttpMode::Record(dir),
+        (_, Some(dir)) => HttpMode::Replay(dir),
+        _ => HttpMode::Live,
+    };
+
     let client = reqwest::Client::builder()
         .user_agent(DEFAULT_USER_AGENT)
         .build()?;
+    let client = HttpClient::new(
+        client,
+        mode,
+        HttpOptions {
+            max_retries: args.retries,
+            requests_per_second: args.requests_per_second,
+            max_concurrent_requests: args.max_concurrent_requests,
+            timeout: Duration::from_secs(args.timeout),
+            ..HttpOptions::default()
+        },
+    );
 
-    let mut joinset: JoinSet<(&'static str, Result<()>)> = JoinSet::new();
-    joinset.spawn(run::<SoundVoltex>(client.clone()));
-    joinset.spawn(run::<PopNMusic>(client.clone()));
-    joinset.spawn(run::<ChunithmJP>(client.clone()));
-    joinset.spawn(run::<ChunithmIntl>(client.clone()));
-    joinset.spawn(run::<Ongeki>(client.clone()));
-    joinset.spawn(run::<MaimaiJP>(client.clone()));
-    joinset.spawn(run::<MaimaiIntl>(client.clone()));
-    joinset.spawn(run::<PolarisChord>(client));
+    let mut joinset: JoinSet<(&'static str, Result<Option<ChangeReport>>)> = JoinSet::new();
+    for_each_game!(|G| {
+        if args.wants(G::name()) {
+            joinset.spawn(run::<G>(client.clone(), Arc::clone(&options)));
+        }
+    });
 
     let mut return_result = Ok(());
+    let mut differing = vec![];
+    let mut reports = vec![];
 
     while let Some(join_result) = joinset.join_next().await {
         let (name, result) = join_result.expect("task panicked");
-        if let Err(err) = result {
-            error!("Task {} failed: {}", name, err);
-            return_result = Err(Error::msg("One or more tasks failed"));
-        } else {
-            info!("Task {} succeeded", name);
+        match result {
+            Ok(report) => {
+                info!("Task {} succeeded", name);
+                if let Some(report) = report {
+                    differing.push(name);
+                    reports.push(report);
+                }
+            }
+            Err(err) => {
+                error!("Task {} failed: {}", name, err);
+                return_result = Err(Error::msg("One or more tasks failed"));
+            }
         }
     }
 
     info!("All fetch completed");
+
+    if let Some(report_path) = &args.report {
+        reports.sort_by_key(|report| all_games.iter().position(|name| *name == report.game));
+        let markdown = report::render(&reports);
+
+        if report_path.as_os_str() == "-" {
+            print!("{markdown}");
+        } else {
+            info!("Writing change report to {:?}", report_path.as_os_str());
+            tokio::fs::write(report_path, markdown).await?;
+        }
+    }
+
+    if args.check && !differing.is_empty() && return_result.is_ok() {
+        differing.sort_unstable();
+        return_result = Err(Error::msg(format!(
+            "Remote data differs for {}",
+            differing.join(", ")
+        )));
+    }
+
     info!("Exiting");
     return_result
 }
 
-async fn run<G>(client: reqwest::Client) -> (&'static str, Result<()>)
+async fn run<G>(
+    client: HttpClient,
+    options: Arc<FetchOptions>,
+) -> (&'static str, Result<Option<ChangeReport>>)
 where
     G: Otoge + FetchTask<G>,
     G::Extractor: Extractor<G>,
-    G::Song: serde::de::DeserializeOwned + std::convert::From<G::ApiSong>,
+    G::Song: serde::de::DeserializeOwned + std::convert::From<G::ApiSong> + SongTrait + Clone,
     G::ApiSong: serde::de::DeserializeOwned,
-    G::DataStore: DataStoreTrait + serde::de::DeserializeOwned + serde::Serialize,
+    G::DataStore: DataStoreTrait<Song = G::Song> + serde::de::DeserializeOwned + serde::Serialize,
 {
-    (G::name(), process::<G>(client).await)
+    let result = async {
+        let report = if options.jackets_only {
+            None
+        } else {
+            process::<G>(client.clone(), &options).await?
+        };
+
+        if options.jackets_only || (options.jackets && !options.dry_run) {
+            jackets::mirror::<G>(&client, &options.data_dir, options.asset_origin.as_ref())
+                .instrument(info_span!("jackets", name = G::name()))
+                .await?;
+        }
+
+        Ok(report)
+    }
+    .await;
+
+    (G::name(), result)
 }
 
-async fn process<G>(client: reqwest::Client) -> Result<()>
+/// Returns the changes between the local data store and the remote data, if there are any
+async fn process<G>(client: HttpClient, options: &FetchOptions) -> Result<Option<ChangeReport>>
 where
     G: Otoge + FetchTask<G>,
     G::Extractor: Extractor<G>,
-    G::Song: serde::de::DeserializeOwned + std::convert::From<G::ApiSong>,
+    G::Song: serde::de::DeserializeOwned + std::convert::From<G::ApiSong> + SongTrait + Clone,
     G::ApiSong: serde::de::DeserializeOwned,
-    G::DataStore: DataStoreTrait + serde::de::DeserializeOwned + serde::Serialize,
+    G::DataStore: DataStoreTrait<Song = G::Song> + serde::de::DeserializeOwned + serde::Serialize,
 {
     let name = G::name();
 
-    let data_path = Path::new(DATA_PATH);
-    let data_dir = G::data_path(Some(data_path));
-    tokio::fs::create_dir_all(&data_dir).await?;
+    let data_path = options.data_dir.as_path();
 
     let local_data_store = load_local_data_store::<G>(Some(data_path))
         .instrument(info_span!("load_local", name))
@@ -115,25 +309,73 @@ where
             None
         });
 
-    let new_data_store = fetch_remote::<G>(&client)
-        .instrument(info_span!("fetch_remote", name))
-        .await?;
+    let id_registry_path = G::id_registry_path(Some(data_path));
+    let mut id_registry = IdRegistry::load(&id_registry_path).await?;
+
+    let new_data_store = fetch_remote::<G>(
+        &client,
+        local_data_store.as_ref(),
+        &mut id_registry,
+        options.fetch_details,
+    )
+    .instrument(info_span!("fetch_remote", name))
+    .await?;
 
     G::verify_categories(&client, &new_data_store).await?;
 
     async {
-        let should_update = if let Some(data_store) = local_data_store {
-            if data_store.data_differs(&new_data_store) {
-                warn!("Local data differs from API, updating...");
+        let diff = local_data_store
+            .as_ref()
+            .map(|data_store| data_store.diff(&new_data_store));
+
+        let should_update = match &diff {
+            Some(diff) if diff.is_empty() => false,
+            Some(diff) => {
+                warn!(
+                    "Local data differs from API (+{} -{} ~{})",
+                    diff.added.len(),
+                    diff.removed.len(),
+                    diff.modified.len()
+                );
+                log_diff(diff);
                 true
-            } else {
-                false
             }
-        } else {
-            true
+            None => true,
         };
 
         if should_update {
+            let previous_count = local_data_store
+                .as_ref()
+                .map(|data_store| data_store.available_songs().count());
+            let new_count = new_data_store.available_songs().count();
+
+            let check = G::safeguard().with_overrides(&options.safeguard).check(
+                previous_count,
+                new_count,
+                diff.as_ref(),
+            );
+            match check {
+                Err(err) if options.allow_mass_removal => {
+                    warn!("Safeguard overridden: {}", err);
+                }
+                result => result?,
+            }
+        }
+
+        let report = should_update.then(|| match (&local_data_store, &diff) {
+            (Some(local_data_store), Some(diff)) => {
+                ChangeReport::new(name, local_data_store.songs(), new_data_store.songs(), diff)
+            }
+            _ => ChangeReport::initial(name, new_data_store.available_songs().count()),
+        });
+
+        if !should_update {
+            info!("Local song list already up-to-date");
+        } else if options.dry_run {
+            info!("Dry run, skipping writing new data");
+        } else {
+            tokio::fs::create_dir_all(G::data_path(Some(data_path))).await?;
+
             let music_data_store_path = G::music_data_store_path(Some(data_path));
             info!(
                 "Writing new data to {:?}",
@@ -141,29 +383,82 @@ where
             );
             let toml_content = toml::to_string(&new_data_store)?;
             tokio::fs::write(&music_data_store_path, &toml_content).await?;
-        } else {
-            info!("Local song list already up-to-date");
+
+            if !id_registry.is_empty() {
+                info!("Writing id registry to {:?}", &id_registry_path.as_os_str());
+                id_registry.save(&id_registry_path).await?;
+            }
+
+            if let Some(diff) = diff {
+                let entry = ChangelogEntry::new(Utc::now(), diff);
+                if !entry.is_empty() {
+                    let changelog_path = G::changelog_path(Some(data_path));
+                    info!("Appending changes to {:?}", &changelog_path.as_os_str());
+                    Changelog::append(&changelog_path, entry).await?;
+                }
+            }
         }
 
         info!("Done");
 
-        Ok::<(), anyhow::Error>(())
+        Ok::<Option<ChangeReport>, anyhow::Error>(report)
     }
     .instrument(info_span!("save", name))
-    .await?;
+    .await
+}
 
-    Ok(())
+fn log_diff(diff: &DataStoreDiff) {
+    for song in &diff.added {
+        info!("Added: {} [{}]", song.title, song.key);
+    }
+
+    for song in &diff.removed {
+        info!("Removed: {} [{}]", song.title, song.key);
+    }
+
+    for song in &diff.modified {
+        for change in &song.changes {
+            info!(
+                "Modified: {} [{}] {}: {} -> {}",
+                song.title,
+                song.key,
+                change.field,
+                change.before.as_deref().unwrap_or("(none)"),
+                change.after.as_deref().unwrap_or("(none)")
+            );
+        }
+    }
+
+    if diff.definitions_changed {
+        info!("Category definitions changed");
+    }
 }
 
-async fn fetch_remote<G>(client: &reqwest::Client) -> Result<G::DataStore>
+async fn fetch_remote<G>(
+    client: &HttpClient,
+    local_data_store: Option<&G::DataStore>,
+    id_registry: &mut IdRegistry,
+    fetch_details: bool,
+) -> Result<G::DataStore>
 where
     G: Otoge + FetchTask<G>,
     G::Extractor: Extractor<G>,
+    G::Song: SongTrait + Clone,
+    G::DataStore: DataStoreTrait<Song = G::Song>,
 {
     info!("Fetching remote song list");
 
-    let songs = G::Extractor::fetch_songs(client).await?;
+    let mut songs = G::Extractor::fetch_songs(client).await?;
     info!("Fetched {} songs", &songs.len());
 
+    G::assign_ids(id_registry, &mut songs);
+
+    let previous_songs = local_data_store
+        .map(|data_store| data_store.songs())
+        .unwrap_or_default();
+    G::enrich(client, previous_songs, &mut songs, fetch_details).await?;
+
+    track_availability(previous_songs, &mut songs, Utc::now().date_naive());
+
     Ok(G::new_data_store(songs))
 }
@@ -4,10 +4,12 @@ use anyhow::{Result, ensure};
 use async_trait::async_trait;
 
 use crate::extractors::serde::SerdeGetExtractor;
+use crate::http::HttpClient;
+use crate::safeguard::Safeguard;
 use crate::traits::FetchTask;
 use otoge::ongeki::Ongeki;
 use otoge::ongeki::models::{DataStore, Song, SongFromAPI};
-use otoge::shared::traits::Otoge;
+use otoge::shared::traits::{DataStore as DataStoreTrait, Otoge};
 
 #[async_trait]
 impl FetchTask<Self> for Ongeki {
@@ -18,16 +20,23 @@ impl FetchTask<Self> for Ongeki {
         "https://ongeki.sega.jp/assets/json/music/music.json"
     }
 
+    fn safeguard() -> Safeguard {
+        Safeguard {
+            min_count: 500,
+            ..Safeguard::default()
+        }
+    }
+
     fn new_data_store(songs: Vec<Song>) -> DataStore {
         DataStore::new(Self::name(), songs)
     }
 
     async fn verify_categories(
-        _client: &reqwest::Client,
+        _client: &HttpClient,
         data_store: &<Self as Otoge>::DataStore,
     ) -> Result<()> {
         let categories = &data_store.categories;
-        let songs = &data_store.songs;
+        let songs = data_store.available_songs();
 
         let all_categories: HashSet<&str> = categories.iter().map(|cat| cat.id.as_ref()).collect();
 
@@ -5,16 +5,18 @@ use async_trait::async_trait;
 use scraper::{Html, Selector};
 
 use crate::extractors::polarischord::PolarisChordExtractor;
+use crate::http::HttpClient;
+use crate::safeguard::Safeguard;
 use crate::traits::FetchTask;
 use otoge::polarischord::PolarisChord;
 use otoge::polarischord::models::{Category, DataStore, Song, SongFromAPI};
 use otoge::shared::traits::Otoge;
 
-async fn fetch_categories(client: &reqwest::Client) -> Result<Vec<Category>> {
+async fn fetch_categories(client: &HttpClient) -> Result<Vec<Category>> {
     let url = "https://p.eagate.573.jp/game/polarischord/pc/music/index.html";
 
-    let resp = client.get(url).send().await?;
-    let html_string = resp.text().await?;
+    let resp = client.get(url).send().await?.error_for_status()?;
+    let html_string = resp.text();
 
     let html = Html::parse_document(html_string.as_str());
     let selector = Selector::parse(r#"div#search > .category_select > select > option"#)
@@ -62,12 +64,19 @@ impl FetchTask<Self> for PolarisChord {
         "https://p.eagate.573.jp/game/polarischord/pc/json/common_getdata.html"
     }
 
+    fn safeguard() -> Safeguard {
+        Safeguard {
+            min_count: 200,
+            ..Safeguard::default()
+        }
+    }
+
     fn new_data_store(songs: Vec<Song>) -> DataStore {
         DataStore::new(Self::name(), songs)
     }
 
     async fn verify_categories(
-        client: &reqwest::Client,
+        client: &HttpClient,
         data_store: &<Self as Otoge>::DataStore,
     ) -> Result<()> {
         let local_categories = data_store.categories.as_slice();
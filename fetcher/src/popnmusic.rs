@@ -4,10 +4,13 @@ use anyhow::{Result, ensure};
 use async_trait::async_trait;
 
 use crate::extractors::popnmusic::PopNMusicExtractor;
+use crate::http::HttpClient;
+use crate::safeguard::Safeguard;
 use crate::traits::FetchTask;
 use otoge::popnmusic::PopNMusic;
 use otoge::popnmusic::models::{DataStore, Song};
-use otoge::shared::traits::Otoge;
+use otoge::shared::ids::IdRegistry;
+use otoge::shared::traits::{DataStore as DataStoreTrait, Otoge};
 
 #[async_trait]
 impl FetchTask<Self> for PopNMusic {
@@ -15,21 +18,32 @@ impl FetchTask<Self> for PopNMusic {
     type Extractor = PopNMusicExtractor;
 
     fn api_url() -> &'static str {
-        "https://p.eagate.573.jp/game/popn/popn29/music/list.html"
+        crate::extractors::popnmusic::SPEC.url
+    }
+
+    fn safeguard() -> Safeguard {
+        Safeguard {
+            min_count: 1000,
+            ..Safeguard::default()
+        }
     }
 
     fn new_data_store(songs: Vec<<Self as Otoge>::Song>) -> <Self as Otoge>::DataStore {
         DataStore::new(Self::name(), songs)
     }
 
+    fn assign_ids(id_registry: &mut IdRegistry, songs: &mut [<Self as Otoge>::Song]) {
+        id_registry.assign(songs);
+    }
+
     async fn verify_categories(
-        _client: &reqwest::Client,
+        _client: &HttpClient,
         data_store: &<Self as Otoge>::DataStore,
     ) -> Result<()> {
         let versions = &data_store.versions;
         let bemani_games = &data_store.bemani;
         let categories = &data_store.categories;
-        let songs = &data_store.songs;
+        let songs = data_store.available_songs();
 
         let valid_version_ids: HashSet<&str> = versions.iter().map(|v| v.id.as_ref()).collect();
         let valid_bemani_ids: HashSet<&str> = bemani_games.iter().map(|b| b.id.as_ref()).collect();
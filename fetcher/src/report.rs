@@ -0,0 +1,271 @@
+use std::collections::HashMap;
+use std::fmt::{self, Display, Write};
+
+use otoge::shared::diff::{DataStoreDiff, SongSummary};
+use otoge::shared::level::Level;
+use otoge::shared::traits::Song as SongTrait;
+
+pub struct AddedSong {
+    pub summary: SongSummary,
+    pub artist: String,
+    pub charts: Vec<(&'static str, Level)>,
+}
+
+pub struct LevelChange {
+    pub summary: SongSummary,
+    pub difficulty: &'static str,
+    pub before: Option<Level>,
+    pub after: Option<Level>,
+}
+
+pub struct CategoryChange {
+    pub summary: SongSummary,
+    pub before: Vec<String>,
+    pub after: Vec<String>,
+}
+
+/// Human-readable changes of a single game, rendered as a Markdown section
+pub struct ChangeReport {
+    pub game: &'static str,
+    /// Number of songs fetched when there was no local data to compare against
+    pub initial_count: Option<usize>,
+    pub added: Vec<AddedSong>,
+    pub removed: Vec<SongSummary>,
+    pub level_changes: Vec<LevelChange>,
+    pub category_changes: Vec<CategoryChange>,
+    pub definitions_changed: bool,
+}
+
+impl ChangeReport {
+    pub fn initial(game: &'static str, count: usize) -> Self {
+        Self {
+            game,
+            initial_count: Some(count),
+            added: vec![],
+            removed: vec![],
+            level_changes: vec![],
+            category_changes: vec![],
+            definitions_changed: false,
+        }
+    }
+
+    pub fn new<S>(
+        game: &'static str,
+        old_songs: &[S],
+        new_songs: &[S],
+        diff: &DataStoreDiff,
+    ) -> Self
+    where
+        S: SongTrait,
+    {
+        let old_by_key = index_by_key(old_songs);
+        let new_by_key = index_by_key(new_songs);
+
+        let added = diff
+            .added
+            .iter()
+            .filter_map(|summary| new_by_key.get(summary.key.as_str()))
+            .map(|song| AddedSong {
+                summary: summary_of(*song),
+                artist: song.artist().to_owned(),
+                charts: song.charts(),
+            })
+            .collect();
+
+        let mut level_changes = vec![];
+        let mut category_changes = vec![];
+
+        for modification in &diff.modified {
+            let key = modification.key.as_str();
+            let (Some(old_song), Some(new_song)) = (old_by_key.get(key), new_by_key.get(key))
+            else {
+                continue;
+            };
+
+            let old_charts = old_song.charts();
+            let new_charts = new_song.charts();
+
+            for (difficulty, after) in &new_charts {
+                let before = level_of(&old_charts, difficulty);
+                if before.as_ref() != Some(after) {
+                    level_changes.push(LevelChange {
+                        summary: summary_of(*new_song),
+                        difficulty,
+                        before,
+                        after: Some(*after),
+                    });
+                }
+            }
+
+            for (difficulty, before) in &old_charts {
+                if level_of(&new_charts, difficulty).is_none() {
+                    level_changes.push(LevelChange {
+                        summary: summary_of(*new_song),
+                        difficulty,
+                        before: Some(*before),
+                        after: None,
+                    });
+                }
+            }
+
+            let before = old_song.category_names();
+            let after = new_song.category_names();
+            if before != after {
+                category_changes.push(CategoryChange {
+                    summary: summary_of(*new_song),
+                    before: before.into_iter().map(str::to_owned).collect(),
+                    after: after.into_iter().map(str::to_owned).collect(),
+                });
+            }
+        }
+
+        Self {
+            game,
+            initial_count: None,
+            added,
+            removed: diff.removed.clone(),
+            level_changes,
+            category_changes,
+            definitions_changed: diff.definitions_changed,
+        }
+    }
+}
+
+impl Display for ChangeReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "## {}", self.game)?;
+
+        if let Some(count) = self.initial_count {
+            writeln!(f)?;
+            writeln!(f, "No previous data, fetched {count} songs.")?;
+            return Ok(());
+        }
+
+        if !self.added.is_empty() {
+            writeln!(f)?;
+            writeln!(f, "### Added ({})", self.added.len())?;
+            writeln!(f)?;
+            writeln!(f, "| Title | Artist | Levels |")?;
+            writeln!(f, "| --- | --- | --- |")?;
+            for song in &self.added {
+                let levels = song
+                    .charts
+                    .iter()
+                    .map(|(difficulty, level)| format!("{difficulty} {level}"))
+                    .collect::<Vec<_>>()
+                    .join(" / ");
+                writeln!(
+                    f,
+                    "| {} | {} | {} |",
+                    escape(&song.summary.title),
+                    escape(&song.artist),
+                    levels
+                )?;
+            }
+        }
+
+        if !self.removed.is_empty() {
+            writeln!(f)?;
+            writeln!(f, "### Removed ({})", self.removed.len())?;
+            writeln!(f)?;
+            for song in &self.removed {
+                writeln!(f, "- {} (`{}`)", escape(&song.title), song.key)?;
+            }
+        }
+
+        if !self.level_changes.is_empty() {
+            writeln!(f)?;
+            writeln!(f, "### Level changes ({})", self.level_changes.len())?;
+            writeln!(f)?;
+            writeln!(f, "| Title | Chart | Before | After |")?;
+            writeln!(f, "| --- | --- | --- | --- |")?;
+            for change in &self.level_changes {
+                writeln!(
+                    f,
+                    "| {} | {} | {} | {} |",
+                    escape(&change.summary.title),
+                    change.difficulty,
+                    display_or_dash(change.before.as_ref()),
+                    display_or_dash(change.after.as_ref())
+                )?;
+            }
+        }
+
+        if !self.category_changes.is_empty() {
+            writeln!(f)?;
+            writeln!(f, "### Category changes ({})", self.category_changes.len())?;
+            writeln!(f)?;
+            writeln!(f, "| Title | Before | After |")?;
+            writeln!(f, "| --- | --- | --- |")?;
+            for change in &self.category_changes {
+                writeln!(
+                    f,
+                    "| {} | {} | {} |",
+                    escape(&change.summary.title),
+                    escape(&change.before.join(", ")),
+                    escape(&change.after.join(", "))
+                )?;
+            }
+        }
+
+        if self.definitions_changed {
+            writeln!(f)?;
+            writeln!(f, "Category definitions changed.")?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Renders the reports of every game that changed into a single Markdown document
+pub fn render(reports: &[ChangeReport]) -> String {
+    let mut markdown = String::from("# Song database update\n");
+
+    if reports.is_empty() {
+        markdown.push_str("\nNo changes.\n");
+    }
+
+    for report in reports {
+        // Writing into a String can't fail
+        let _ = write!(markdown, "\n{report}");
+    }
+
+    markdown
+}
+
+fn index_by_key<S>(songs: &[S]) -> HashMap<String, &S>
+where
+    S: SongTrait,
+{
+    songs
+        .iter()
+        .filter(|song| !song.is_removed())
+        .map(|song| (song.key().into_owned(), song))
+        .collect()
+}
+
+fn summary_of<S>(song: &S) -> SongSummary
+where
+    S: SongTrait,
+{
+    SongSummary {
+        key: song.key().into_owned(),
+        title: song.title().to_owned(),
+    }
+}
+
+fn level_of(charts: &[(&'static str, Level)], difficulty: &str) -> Option<Level> {
+    charts
+        .iter()
+        .find(|(d, _)| *d == difficulty)
+        .map(|(_, level)| *level)
+}
+
+fn display_or_dash(level: Option<&Level>) -> String {
+    level.map_or_else(|| "-".to_owned(), Level::to_string)
+}
+
+/// Keeps song titles from breaking out of their table cell
+fn escape(text: &str) -> String {
+    text.replace('|', "\\|").replace('\n', " ")
+}
@@ -0,0 +1,207 @@
+use anyhow::{Result, bail};
+use otoge::shared::diff::DataStoreDiff;
+
+/// Thresholds protecting a data store from being overwritten by a truncated upstream song list,
+/// e.g. a half-empty maintenance page.
+#[derive(Debug, Clone, Copy)]
+pub struct Safeguard {
+    /// Maximum number of songs removed in a single fetch
+    pub max_removed: usize,
+    /// Maximum drop of the song count in a single fetch, in percent of the previous count
+    pub max_drop_percent: f64,
+    /// Minimum number of songs the fetched list must contain
+    pub min_count: usize,
+}
+
+impl Default for Safeguard {
+    fn default() -> Self {
+        Self {
+            max_removed: 50,
+            max_drop_percent: 10.0,
+            min_count: 0,
+        }
+    }
+}
+
+/// Thresholds given on the command line, replacing the ones of every fetched game
+#[derive(clap::Args, Debug, Clone, Copy, Default)]
+pub struct SafeguardOverrides {
+    /// Maximum number of songs a single fetch may remove, instead of the game's threshold
+    #[arg(long, value_name = "N")]
+    pub max_removed: Option<usize>,
+
+    /// Maximum drop of the song count in percent, instead of the game's threshold
+    #[arg(long, value_name = "PERCENT")]
+    pub max_drop_percent: Option<f64>,
+
+    /// Minimum number of songs the fetched list must contain, instead of the game's threshold
+    #[arg(long, value_name = "N")]
+    pub min_count: Option<usize>,
+}
+
+impl Safeguard {
+    pub fn with_overrides(self, overrides: &SafeguardOverrides) -> Self {
+        Self {
+            max_removed: overrides.max_removed.unwrap_or(self.max_removed),
+            max_drop_percent: overrides.max_drop_percent.unwrap_or(self.max_drop_percent),
+            min_count: overrides.min_count.unwrap_or(self.min_count),
+        }
+    }
+
+    /// Fails if the new song list trips any of the thresholds
+    pub fn check(
+        &self,
+        previous_count: Option<usize>,
+        new_count: usize,
+        diff: Option<&DataStoreDiff>,
+    ) -> Result<()> {
+        let mut violations = vec![];
+
+        if new_count < self.min_count {
+            violations.push(format!(
+                "only {} songs fetched, expected at least {}",
+                new_count, self.min_count
+            ));
+        }
+
+        if let Some(diff) = diff
+            && diff.removed.len() > self.max_removed
+        {
+            violations.push(format!(
+                "{} songs removed, at most {} allowed",
+                diff.removed.len(),
+                self.max_removed
+            ));
+        }
+
+        if let Some(previous_count) = previous_count
+            && previous_count > 0
+            && new_count < previous_count
+        {
+            let drop_percent = (previous_count - new_count) as f64 / previous_count as f64 * 100.0;
+            if drop_percent > self.max_drop_percent {
+                violations.push(format!(
+                    "song count dropped by {:.1}% ({} -> {}), at most {}% allowed",
+                    drop_percent, previous_count, new_count, self.max_drop_percent
+                ));
+            }
+        }
+
+        if violations.is_empty() {
+            return Ok(());
+        }
+
+        let summary = match diff {
+            Some(diff) => format!(
+                "+{} -{} ~{}",
+                diff.added.len(),
+                diff.removed.len(),
+                diff.modified.len()
+            ),
+            None => "no local data".to_owned(),
+        };
+
+        bail!(
+            "Refusing to write new data ({}): {}; pass --allow-mass-removal to override",
+            summary,
+            violations.join(", ")
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use otoge::shared::diff::SongSummary;
+
+    use super::*;
+
+    fn removing(count: usize) -> DataStoreDiff {
+        DataStoreDiff {
+            removed: (0..count)
+                .map(|i| SongSummary {
+                    key: i.to_string(),
+                    title: format!("Song {i}"),
+                })
+                .collect(),
+            ..DataStoreDiff::default()
+        }
+    }
+
+    #[test]
+    fn allows_normal_updates() {
+        let safeguard = Safeguard::default();
+
+        assert!(
+            safeguard
+                .check(Some(1000), 1010, Some(&removing(5)))
+                .is_ok()
+        );
+        assert!(safeguard.check(None, 1000, None).is_ok());
+    }
+
+    #[test]
+    fn rejects_too_many_removals() {
+        let safeguard = Safeguard::default();
+
+        assert!(
+            safeguard
+                .check(Some(1000), 1000, Some(&removing(50)))
+                .is_ok()
+        );
+        let err = safeguard
+            .check(Some(1000), 1000, Some(&removing(51)))
+            .unwrap_err();
+        assert!(err.to_string().contains("51 songs removed"), "{err}");
+    }
+
+    #[test]
+    fn rejects_large_count_drops() {
+        let safeguard = Safeguard {
+            max_removed: usize::MAX,
+            ..Safeguard::default()
+        };
+
+        assert!(safeguard.check(Some(1000), 900, None).is_ok());
+        let err = safeguard.check(Some(1000), 899, None).unwrap_err();
+        assert!(err.to_string().contains("dropped by 10.1%"), "{err}");
+    }
+
+    #[test]
+    fn rejects_short_song_lists() {
+        let safeguard = Safeguard {
+            min_count: 700,
+            ..Safeguard::default()
+        };
+
+        assert!(safeguard.check(None, 700, None).is_ok());
+        let err = safeguard.check(None, 12, None).unwrap_err();
+        assert!(err.to_string().contains("only 12 songs fetched"), "{err}");
+        assert!(err.to_string().contains("no local data"), "{err}");
+    }
+
+    #[test]
+    fn ignores_drops_from_empty_data_stores() {
+        assert!(Safeguard::default().check(Some(0), 0, None).is_ok());
+    }
+
+    #[test]
+    fn overrides_replace_only_given_thresholds() {
+        let safeguard = Safeguard {
+            min_count: 700,
+            ..Safeguard::default()
+        }
+        .with_overrides(&SafeguardOverrides {
+            max_removed: Some(500),
+            ..SafeguardOverrides::default()
+        });
+
+        assert_eq!(safeguard.max_removed, 500);
+        assert_eq!(safeguard.max_drop_percent, 10.0);
+        assert_eq!(safeguard.min_count, 700);
+        assert!(
+            safeguard
+                .check(Some(1000), 1000, Some(&removing(400)))
+                .is_ok()
+        );
+    }
+}
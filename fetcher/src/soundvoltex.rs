@@ -3,9 +3,12 @@ use std::collections::HashSet;
 use anyhow::{Result, ensure};
 use async_trait::async_trait;
 
-use crate::extractors::soundvoltex_vii::SoundVoltexVIIExtractor;
+use crate::extractors::soundvoltex_vii::{SoundVoltexVIIExtractor, enrich_songs};
+use crate::http::HttpClient;
+use crate::safeguard::Safeguard;
 use crate::traits::FetchTask;
-use otoge::shared::traits::Otoge;
+use otoge::shared::ids::IdRegistry;
+use otoge::shared::traits::{DataStore as DataStoreTrait, Otoge};
 use otoge::soundvoltex::SoundVoltex;
 use otoge::soundvoltex::models::{DataStore, Song};
 
@@ -18,16 +21,36 @@ impl FetchTask<Self> for SoundVoltex {
         "https://p.eagate.573.jp/game/sdvx/vii/music/index.html"
     }
 
+    fn safeguard() -> Safeguard {
+        Safeguard {
+            min_count: 1000,
+            ..Safeguard::default()
+        }
+    }
+
     fn new_data_store(songs: Vec<<Self as Otoge>::Song>) -> <Self as Otoge>::DataStore {
         DataStore::new(Self::name(), songs)
     }
 
+    fn assign_ids(id_registry: &mut IdRegistry, songs: &mut [<Self as Otoge>::Song]) {
+        id_registry.assign(songs);
+    }
+
+    async fn enrich(
+        client: &HttpClient,
+        local_songs: &[Song],
+        songs: &mut [Song],
+        fetch_details: bool,
+    ) -> Result<()> {
+        enrich_songs(client, local_songs, songs, fetch_details).await
+    }
+
     async fn verify_categories(
-        _client: &reqwest::Client,
+        _client: &HttpClient,
         data_store: &<Self as Otoge>::DataStore,
     ) -> Result<()> {
         let categories = &data_store.categories;
-        let songs = &data_store.songs;
+        let songs = data_store.available_songs();
 
         let all_categories: HashSet<(&str, &str)> = categories
             .iter()
@@ -1,8 +1,12 @@
 use anyhow::Result;
 
 use async_trait::async_trait;
+use otoge::shared::ids::IdRegistry;
 use otoge::shared::traits::Otoge;
 
+use crate::http::HttpClient;
+use crate::safeguard::Safeguard;
+
 #[async_trait]
 pub trait FetchTask<G>
 where
@@ -14,12 +18,22 @@ where
     fn api_url() -> &'static str;
 
     fn new_data_store(songs: Vec<G::Song>) -> G::DataStore;
-    async fn verify_categories(
-        _client: &reqwest::Client,
-        _data_store: &G::DataStore,
+    fn safeguard() -> Safeguard {
+        Safeguard::default()
+    }
+    fn assign_ids(_id_registry: &mut IdRegistry, _songs: &mut [G::Song]) {}
+    /// Adds data missing from the song list, extra pages are only fetched with `fetch_details`
+    async fn enrich(
+        _client: &HttpClient,
+        _local_songs: &[G::Song],
+        _songs: &mut [G::Song],
+        _fetch_details: bool,
     ) -> Result<()> {
         Ok(())
     }
+    async fn verify_categories(_client: &HttpClient, _data_store: &G::DataStore) -> Result<()> {
+        Ok(())
+    }
 }
 
 #[async_trait]
@@ -27,5 +41,5 @@ pub trait Extractor<G>
 where
     G: Otoge + FetchTask<G>,
 {
-    async fn fetch_songs(client: &reqwest::Client) -> Result<Vec<G::Song>>;
+    async fn fetch_songs(client: &HttpClient) -> Result<Vec<G::Song>>;
 }
@@ -0,0 +1,202 @@
+use std::sync::Arc;
+
+use anyhow::Result;
+use arrow::array::{ArrayRef, Date32Builder, Float64Builder, ListBuilder, StringBuilder};
+use arrow::datatypes::{DataType, Date32Type, Field, Schema};
+use arrow::record_batch::RecordBatch;
+use chrono::NaiveDate;
+use otoge::shared::level::Level;
+use otoge::shared::traits::{DataStore, Song};
+use parquet::arrow::ArrowWriter;
+use parquet::basic::Compression;
+use parquet::file::properties::WriterProperties;
+use serde::Serialize;
+
+use crate::formats::OutputFormat;
+
+/// One row per song, with a level and a numeric level column per difficulty of the game.
+/// The difficulty columns come from [`Song::DIFFICULTIES`] so the schema doesn't depend on the
+/// songs in the data store
+pub struct Parquet;
+
+impl OutputFormat for Parquet {
+    const EXTENSION: &'static str = "parquet";
+
+    fn render<D>(data_store: &D) -> Result<Vec<u8>>
+    where
+        D: DataStore + Serialize,
+        D::Song: Serialize,
+    {
+        let batch = record_batch(data_store.songs())?;
+
+        let properties = WriterProperties::builder()
+            .set_compression(Compression::SNAPPY)
+            .build();
+
+        let mut output = vec![];
+        let mut writer = ArrowWriter::try_new(&mut output, batch.schema(), Some(properties))?;
+        writer.write(&batch)?;
+        writer.close()?;
+
+        Ok(output)
+    }
+}
+
+fn record_batch<S>(songs: &[S]) -> Result<RecordBatch>
+where
+    S: Song,
+{
+    let mut fields = vec![
+        Field::new("key", DataType::Utf8, false),
+        Field::new("title", DataType::Utf8, false),
+        Field::new("title_reading", DataType::Utf8, true),
+        Field::new("artist", DataType::Utf8, false),
+        Field::new_list(
+            "categories",
+            Field::new_list_field(DataType::Utf8, false),
+            false,
+        ),
+        Field::new_list(
+            "versions",
+            Field::new_list_field(DataType::Utf8, false),
+            false,
+        ),
+        Field::new("release_date", DataType::Date32, true),
+        Field::new("first_seen", DataType::Date32, true),
+        Field::new("removed_at", DataType::Date32, true),
+    ];
+    let mut columns = vec![
+        strings(songs.iter().map(|song| Some(song.key()))),
+        strings(songs.iter().map(|song| Some(song.title()))),
+        strings(songs.iter().map(|song| song.title_reading())),
+        strings(songs.iter().map(|song| Some(song.artist()))),
+        string_lists(songs.iter().map(|song| song.category_names())),
+        string_lists(songs.iter().map(|song| song.versions())),
+        dates(songs.iter().map(|song| song.release_date())),
+        dates(songs.iter().map(|song| song.first_seen())),
+        dates(songs.iter().map(|song| song.removed_at())),
+    ];
+
+    for difficulty in S::DIFFICULTIES.iter().copied() {
+        let levels: Vec<Option<Level>> = songs
+            .iter()
+            .map(|song| {
+                song.charts()
+                    .into_iter()
+                    .find(|(chart, _)| *chart == difficulty)
+                    .map(|(_, level)| level)
+            })
+            .collect();
+
+        fields.push(Field::new(
+            format!("{difficulty}_level"),
+            DataType::Utf8,
+            true,
+        ));
+        columns.push(strings(
+            levels
+                .iter()
+                .map(|level| level.map(|level| level.to_string())),
+        ));
+
+        fields.push(Field::new(
+            format!("{difficulty}_level_value"),
+            DataType::Float64,
+            true,
+        ));
+        let mut values = Float64Builder::new();
+        for level in &levels {
+            values.append_option(level.and_then(|level| level.numeric()));
+        }
+        columns.push(Arc::new(values.finish()));
+    }
+
+    Ok(RecordBatch::try_new(
+        Arc::new(Schema::new(fields)),
+        columns,
+    )?)
+}
+
+fn strings<I, T>(values: I) -> ArrayRef
+where
+    I: Iterator<Item = Option<T>>,
+    T: AsRef<str>,
+{
+    let mut builder = StringBuilder::new();
+    for value in values {
+        builder.append_option(value);
+    }
+    Arc::new(builder.finish())
+}
+
+fn string_lists<'a, I>(lists: I) -> ArrayRef
+where
+    I: Iterator<Item = Vec<&'a str>>,
+{
+    let mut builder = ListBuilder::new(StringBuilder::new())
+        .with_field(Field::new_list_field(DataType::Utf8, false));
+    for list in lists {
+        for value in list {
+            builder.values().append_value(value);
+        }
+        builder.append(true);
+    }
+    Arc::new(builder.finish())
+}
+
+fn dates<I>(values: I) -> ArrayRef
+where
+    I: Iterator<Item = Option<NaiveDate>>,
+{
+    let mut builder = Date32Builder::new();
+    for value in values {
+        builder.append_option(value.map(Date32Type::from_naive_date));
+    }
+    Arc::new(builder.finish())
+}
+
+#[cfg(test)]
+mod tests {
+    use otoge::helpers::load_local_data_store;
+    use otoge::maimai::MaimaiJP;
+    use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn reads_back_a_level_pair_per_difficulty() -> Result<()> {
+        let data_path = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("../data");
+        let data_store = load_local_data_store::<MaimaiJP>(Some(&data_path))
+            .await?
+            .expect("maimai data should be checked in");
+
+        let output = Parquet::render(&data_store)?;
+        let reader = ParquetRecordBatchReaderBuilder::try_new(bytes::Bytes::from(output))?;
+
+        let schema = reader.schema().clone();
+        let level_columns: Vec<&str> = schema
+            .fields()
+            .iter()
+            .map(|field| field.name().as_str())
+            .skip_while(|name| *name != "removed_at")
+            .skip(1)
+            .collect();
+        let expected: Vec<String> = otoge::maimai::models::Song::DIFFICULTIES
+            .iter()
+            .flat_map(|difficulty| {
+                [
+                    format!("{difficulty}_level"),
+                    format!("{difficulty}_level_value"),
+                ]
+            })
+            .collect();
+        assert_eq!(level_columns, expected);
+
+        let rows: usize = reader
+            .build()?
+            .map(|batch| Ok(batch?.num_rows()))
+            .sum::<Result<_>>()?;
+        assert_eq!(rows, data_store.songs().len());
+        Ok(())
+    }
+}
@@ -0,0 +1,170 @@
+use anyhow::Result;
+use clap::ValueEnum;
+use otoge::shared::traits::{DataStore, Song};
+use serde::Serialize;
+
+#[cfg(feature = "parquet")]
+use crate::columnar;
+
+/// A file format the music index can be written in
+pub trait OutputFormat {
+    const EXTENSION: &'static str;
+
+    fn render<D>(data_store: &D) -> Result<Vec<u8>>
+    where
+        D: DataStore + Serialize,
+        D::Song: Serialize;
+}
+
+/// The whole data store as minified JSON
+pub struct Json;
+
+impl OutputFormat for Json {
+    const EXTENSION: &'static str = "json";
+
+    fn render<D>(data_store: &D) -> Result<Vec<u8>>
+    where
+        D: DataStore + Serialize,
+        D::Song: Serialize,
+    {
+        Ok(serde_json::to_vec(data_store)?)
+    }
+}
+
+/// One JSON song per line
+pub struct Ndjson;
+
+impl OutputFormat for Ndjson {
+    const EXTENSION: &'static str = "ndjson";
+
+    fn render<D>(data_store: &D) -> Result<Vec<u8>>
+    where
+        D: DataStore + Serialize,
+        D::Song: Serialize,
+    {
+        let mut output = vec![];
+        for song in data_store.songs() {
+            serde_json::to_writer(&mut output, song)?;
+            output.push(b'\n');
+        }
+        Ok(output)
+    }
+}
+
+/// The whole data store as YAML
+pub struct Yaml;
+
+impl OutputFormat for Yaml {
+    const EXTENSION: &'static str = "yaml";
+
+    fn render<D>(data_store: &D) -> Result<Vec<u8>>
+    where
+        D: DataStore + Serialize,
+        D::Song: Serialize,
+    {
+        Ok(serde_norway::to_string(data_store)?.into_bytes())
+    }
+}
+
+/// The whole data store as MessagePack, with field names so clients don't depend on field order
+pub struct MessagePack;
+
+impl OutputFormat for MessagePack {
+    const EXTENSION: &'static str = "msgpack";
+
+    fn render<D>(data_store: &D) -> Result<Vec<u8>>
+    where
+        D: DataStore + Serialize,
+        D::Song: Serialize,
+    {
+        Ok(rmp_serde::to_vec_named(data_store)?)
+    }
+}
+
+/// One row per chart, with the song columns repeated on every chart of the song
+pub struct Csv;
+
+#[derive(Serialize)]
+struct ChartRow<'a> {
+    key: &'a str,
+    title: &'a str,
+    artist: &'a str,
+    categories: String,
+    versions: String,
+    release_date: Option<String>,
+    removed_at: Option<String>,
+    difficulty: &'static str,
+    level: String,
+}
+
+impl OutputFormat for Csv {
+    const EXTENSION: &'static str = "csv";
+
+    fn render<D>(data_store: &D) -> Result<Vec<u8>>
+    where
+        D: DataStore + Serialize,
+        D::Song: Serialize,
+    {
+        let mut writer = csv::Writer::from_writer(vec![]);
+
+        for song in data_store.songs() {
+            let key = song.key();
+            for (difficulty, level) in song.charts() {
+                writer.serialize(ChartRow {
+                    key: &key,
+                    title: song.title(),
+                    artist: song.artist(),
+                    categories: song.category_names().join(", "),
+                    versions: song.versions().join(", "),
+                    release_date: song.release_date().map(|date| date.to_string()),
+                    removed_at: song.removed_at().map(|date| date.to_string()),
+                    difficulty,
+                    level: level.to_string(),
+                })?;
+            }
+        }
+
+        Ok(writer.into_inner()?)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Format {
+    Json,
+    Ndjson,
+    Csv,
+    Yaml,
+    Msgpack,
+    #[cfg(feature = "parquet")]
+    Parquet,
+}
+
+impl Format {
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Json => Json::EXTENSION,
+            Self::Ndjson => Ndjson::EXTENSION,
+            Self::Csv => Csv::EXTENSION,
+            Self::Yaml => Yaml::EXTENSION,
+            Self::Msgpack => MessagePack::EXTENSION,
+            #[cfg(feature = "parquet")]
+            Self::Parquet => columnar::Parquet::EXTENSION,
+        }
+    }
+
+    pub fn render<D>(self, data_store: &D) -> Result<Vec<u8>>
+    where
+        D: DataStore + Serialize,
+        D::Song: Serialize,
+    {
+        match self {
+            Self::Json => Json::render(data_store),
+            Self::Ndjson => Ndjson::render(data_store),
+            Self::Csv => Csv::render(data_store),
+            Self::Yaml => Yaml::render(data_store),
+            Self::Msgpack => MessagePack::render(data_store),
+            #[cfg(feature = "parquet")]
+            Self::Parquet => columnar::Parquet::render(data_store),
+        }
+    }
+}
@@ -1,8 +1,14 @@
-mod traits;
+#[cfg(feature = "parquet")]
+mod columnar;
+mod formats;
+mod readme;
+mod site;
+mod sqlite;
 
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 use anyhow::{Error, Result, anyhow};
+use clap::{Parser, Subcommand};
 use otoge::helpers::load_local_data_store;
 use serde::Serialize;
 use serde::de::DeserializeOwned;
@@ -11,27 +17,52 @@ use tracing::metadata::LevelFilter;
 use tracing::{Instrument, error, info, info_span};
 use tracing_subscriber::EnvFilter;
 
-use otoge::chunithm::{ChunithmIntl, ChunithmJP};
-use otoge::maimai::{MaimaiIntl, MaimaiJP};
-use otoge::ongeki::Ongeki;
-use otoge::popnmusic::PopNMusic;
+use formats::Format;
+use otoge::for_each_game;
 use otoge::shared::traits::{DataStore, Otoge};
-use otoge::soundvoltex::SoundVoltex;
-use traits::GenerateTask;
+use readme::GameRow;
+use site::SiteExport;
+use sqlite::SqliteExport;
 
 const DATA_PATH: &str = "./data";
 const GENERATED_PATH: &str = "./generated";
 
-impl GenerateTask<Self> for ChunithmJP {}
-impl GenerateTask<Self> for ChunithmIntl {}
-impl GenerateTask<Self> for Ongeki {}
-impl GenerateTask<Self> for MaimaiJP {}
-impl GenerateTask<Self> for MaimaiIntl {}
-impl GenerateTask<Self> for PopNMusic {}
-impl GenerateTask<Self> for SoundVoltex {}
+#[derive(Parser, Debug)]
+#[command(version, about = "Generate derived files from the local data stores")]
+struct Args {
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    /// Formats the music indexes are written in, comma separated
+    #[arg(long, value_enum, value_delimiter = ',', default_value = "json")]
+    format: Vec<Format>,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Write the music indexes into ./generated, the default
+    Music,
+    /// Rewrite the song database table of the README
+    Readme {
+        #[arg(long, default_value = "README.md")]
+        path: PathBuf,
+    },
+    /// Write every game into a single SQLite database
+    Sqlite {
+        #[arg(long, default_value = "generated/otoge.sqlite")]
+        path: PathBuf,
+    },
+    /// Render a static HTML song browser that works offline
+    Site {
+        #[arg(long, default_value = "generated/site")]
+        path: PathBuf,
+    },
+}
 
 #[tokio::main]
 async fn main() -> Result<()> {
+    let args = Args::parse();
+
     let format = tracing_subscriber::fmt::format().with_target(false);
     let filter = EnvFilter::builder()
         .with_default_directive(LevelFilter::INFO.into())
@@ -48,14 +79,17 @@ async fn main() -> Result<()> {
         env!("CARGO_PKG_VERSION")
     );
 
+    match &args.command {
+        Some(Command::Readme { path }) => return update_readme(path).await,
+        Some(Command::Sqlite { path }) => return export_sqlite(path).await,
+        Some(Command::Site { path }) => return export_site(path).await,
+        Some(Command::Music) | None => {}
+    }
+
     let mut joinset: JoinSet<(&'static str, Result<()>)> = JoinSet::new();
-    joinset.spawn(run::<SoundVoltex>());
-    joinset.spawn(run::<PopNMusic>());
-    joinset.spawn(run::<ChunithmJP>());
-    joinset.spawn(run::<ChunithmIntl>());
-    joinset.spawn(run::<Ongeki>());
-    joinset.spawn(run::<MaimaiJP>());
-    joinset.spawn(run::<MaimaiIntl>());
+    for_each_game!(|G| {
+        joinset.spawn(run::<G>(args.format.clone()));
+    });
 
     let mut return_result = Ok(());
 
@@ -74,18 +108,20 @@ async fn main() -> Result<()> {
     return_result
 }
 
-async fn run<G>() -> (&'static str, Result<()>)
+async fn run<G>(formats: Vec<Format>) -> (&'static str, Result<()>)
 where
-    G: Otoge + GenerateTask<G>,
+    G: Otoge,
     G::DataStore: DataStore + DeserializeOwned + Serialize,
+    <G::DataStore as DataStore>::Song: Serialize,
 {
-    (G::name(), process::<G>().await)
+    (G::name(), process::<G>(&formats).await)
 }
 
-async fn process<G>() -> Result<()>
+async fn process<G>(formats: &[Format]) -> Result<()>
 where
-    G: Otoge + GenerateTask<G>,
+    G: Otoge,
     G::DataStore: DataStore + DeserializeOwned + Serialize,
+    <G::DataStore as DataStore>::Song: Serialize,
 {
     let name = G::name();
     let data_path = Path::new(DATA_PATH);
@@ -101,13 +137,15 @@ where
 
             tokio::fs::create_dir_all(&music_dir).await?;
 
-            let out_path = music_dir.join(format!("{name}.json"));
+            for format in formats {
+                let out_path = music_dir.join(format!("{name}.{}", format.extension()));
 
-            info!("Generating music index");
-            let json_content = serde_json::to_string(&data_store)?;
+                info!("Generating music index as {:?}", format);
+                let content = format.render(&data_store)?;
 
-            info!("Saving output to {:?}", &out_path);
-            tokio::fs::write(&out_path, &json_content).await?;
+                info!("Saving output to {:?}", &out_path);
+                tokio::fs::write(&out_path, &content).await?;
+            }
 
             Ok(())
         } else {
@@ -120,3 +158,70 @@ where
     .instrument(info_span!("write_output", name))
     .await
 }
+
+async fn update_readme(readme_path: &Path) -> Result<()> {
+    let data_path = Path::new(DATA_PATH);
+
+    let mut rows = vec![];
+    for_each_game!(|G| {
+        rows.extend(GameRow::load::<G>(data_path).await?);
+    });
+    let table = readme::render_table(rows);
+
+    let readme = tokio::fs::read_to_string(readme_path).await?;
+    let updated = readme::replace_table(&readme, &table)?;
+
+    if updated == readme {
+        info!("README song table already up-to-date");
+    } else {
+        info!("Writing song table to {:?}", readme_path.as_os_str());
+        tokio::fs::write(readme_path, updated).await?;
+    }
+
+    info!("Exiting");
+    Ok(())
+}
+
+async fn export_sqlite(out_path: &Path) -> Result<()> {
+    let data_path = Path::new(DATA_PATH);
+
+    if let Some(parent) = out_path.parent() {
+        tokio::fs::create_dir_all(parent).await?;
+    }
+
+    info!("Creating SQLite database at {:?}", out_path.as_os_str());
+    let mut export = SqliteExport::create(out_path)?;
+
+    for_each_game!(|G| {
+        let name = G::name();
+        let data_store = load_local_data_store::<G>(Some(data_path))
+            .instrument(info_span!("load_local", name))
+            .await?
+            .ok_or_else(|| anyhow!("Could not find music data store for {name}"))?;
+
+        info!("Inserting {}", name);
+        export.insert::<G>(&data_store)?;
+    });
+
+    info!("Exiting");
+    Ok(())
+}
+
+async fn export_site(out_path: &Path) -> Result<()> {
+    let data_path = Path::new(DATA_PATH);
+
+    info!("Rendering static site to {:?}", out_path.as_os_str());
+    let mut export = SiteExport::create(out_path).await?;
+
+    for_each_game!(|G| {
+        let name = G::name();
+        export
+            .add::<G>(data_path)
+            .instrument(info_span!("site", name))
+            .await?;
+    });
+    export.finish().await?;
+
+    info!("Exiting");
+    Ok(())
+}
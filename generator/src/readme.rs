@@ -0,0 +1,133 @@
+use std::path::Path;
+
+use anyhow::{Result, bail};
+use otoge::helpers::load_local_data_store;
+use otoge::shared::traits::{DataStore, Otoge};
+use serde::de::DeserializeOwned;
+
+const START_MARKER: &str = "<!-- SONG_DB_START -->";
+const END_MARKER: &str = "<!-- SONG_DB_END -->";
+
+pub struct GameRow {
+    name: &'static str,
+    cells: [String; 4],
+}
+
+impl GameRow {
+    /// Summarises the local data store of the game, `None` if the game hasn't been fetched yet
+    pub async fn load<G>(data_path: &Path) -> Result<Option<Self>>
+    where
+        G: Otoge,
+        G::DataStore: DataStore + DeserializeOwned,
+    {
+        if !tokio::fs::try_exists(G::music_data_store_path(Some(data_path))).await? {
+            return Ok(None);
+        }
+
+        let Some(data_store) = load_local_data_store::<G>(Some(data_path)).await? else {
+            return Ok(None);
+        };
+
+        Ok(Some(Self {
+            name: G::name(),
+            cells: [
+                format!("[{}]({})", G::display_name(), G::homepage()),
+                data_store.available_songs().count().to_string(),
+                data_store.last_updated().format("%Y-%m-%d").to_string(),
+                // Relative to the README at the root of the repository
+                format!("[View](data/{}/music.toml)", G::name()),
+            ],
+        }))
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Align {
+    Left,
+    Right,
+    Center,
+}
+
+const COLUMNS: [(&str, Align); 4] = [
+    ("Game", Align::Left),
+    ("Songs", Align::Right),
+    ("Last Updated", Align::Center),
+    ("Database", Align::Center),
+];
+
+/// Renders the song database table, rows are sorted by game name
+pub fn render_table(mut rows: Vec<GameRow>) -> String {
+    rows.sort_by_key(|row| row.name);
+
+    let widths: Vec<usize> = COLUMNS
+        .iter()
+        .enumerate()
+        .map(|(i, (header, _))| {
+            rows.iter()
+                .map(|row| row.cells[i].chars().count())
+                .chain([header.chars().count(), 3])
+                .max()
+                .unwrap_or_default()
+        })
+        .collect();
+
+    let line = |cells: Vec<String>| format!("| {} |\n", cells.join(" | "));
+
+    let mut table = line(
+        COLUMNS
+            .iter()
+            .zip(&widths)
+            .map(|((header, align), width)| pad(header, *width, *align))
+            .collect(),
+    );
+    table.push_str(&line(
+        COLUMNS
+            .iter()
+            .zip(&widths)
+            .map(|((_, align), width)| match align {
+                Align::Left => "-".repeat(*width),
+                Align::Right => format!("{}:", "-".repeat(width - 1)),
+                Align::Center => format!(":{}:", "-".repeat(width - 2)),
+            })
+            .collect(),
+    ));
+
+    for row in &rows {
+        table.push_str(&line(
+            row.cells
+                .iter()
+                .zip(COLUMNS.iter().zip(&widths))
+                .map(|(cell, ((_, align), width))| pad(cell, *width, *align))
+                .collect(),
+        ));
+    }
+
+    table
+}
+
+fn pad(text: &str, width: usize, align: Align) -> String {
+    let space = width.saturating_sub(text.chars().count());
+    let (left, right) = match align {
+        Align::Left => (0, space),
+        Align::Right => (space, 0),
+        Align::Center => (space / 2, space - space / 2),
+    };
+
+    format!("{}{text}{}", " ".repeat(left), " ".repeat(right))
+}
+
+/// Replaces everything between the song database markers of the README with the table
+pub fn replace_table(readme: &str, table: &str) -> Result<String> {
+    let (Some(start), Some(end)) = (readme.find(START_MARKER), readme.find(END_MARKER)) else {
+        bail!("README is missing the {START_MARKER} and {END_MARKER} markers");
+    };
+    if end < start {
+        bail!("{END_MARKER} found before {START_MARKER} in README");
+    }
+
+    Ok(format!(
+        "{}{START_MARKER}\n\n{table}\n{}",
+        &readme[..start],
+        &readme[end..]
+    ))
+}
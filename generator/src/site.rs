@@ -0,0 +1,403 @@
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+use otoge::helpers::load_local_data_store;
+use otoge::shared::jackets::JacketManifest;
+use otoge::shared::traits::{DataStore, Otoge, Song};
+use serde::de::DeserializeOwned;
+use tracing::info;
+
+const STYLE: &str = include_str!("site/style.css");
+const SCRIPT: &str = include_str!("site/browser.js");
+
+struct GameLink {
+    name: &'static str,
+    display_name: &'static str,
+    songs: usize,
+    last_updated: String,
+}
+
+/// Static song browser with one page per game, every asset is written next to the pages so
+/// the site works offline and from any subdirectory
+pub struct SiteExport {
+    path: PathBuf,
+    games: Vec<GameLink>,
+}
+
+impl SiteExport {
+    pub async fn create(path: &Path) -> Result<Self> {
+        tokio::fs::create_dir_all(path).await?;
+        tokio::fs::write(path.join("style.css"), STYLE).await?;
+        tokio::fs::write(path.join("browser.js"), SCRIPT).await?;
+
+        Ok(Self {
+            path: path.to_owned(),
+            games: vec![],
+        })
+    }
+
+    /// Writes the song page of the game, games that haven't been fetched yet are left out
+    pub async fn add<G>(&mut self, data_path: &Path) -> Result<()>
+    where
+        G: Otoge,
+        G::DataStore: DataStore + DeserializeOwned,
+    {
+        if !tokio::fs::try_exists(G::music_data_store_path(Some(data_path))).await? {
+            return Ok(());
+        }
+
+        let Some(data_store) = load_local_data_store::<G>(Some(data_path)).await? else {
+            return Ok(());
+        };
+
+        let jackets = self.copy_jackets::<G>(data_path).await?;
+        let page = render_game_page::<G>(&data_store, &jackets);
+
+        let page_path = self.path.join(format!("{}.html", G::name()));
+        info!("Writing song page to {:?}", page_path.as_os_str());
+        tokio::fs::write(page_path, page).await?;
+
+        self.games.push(GameLink {
+            name: G::name(),
+            display_name: G::display_name(),
+            songs: data_store.available_songs().count(),
+            last_updated: data_store.last_updated().format("%Y-%m-%d").to_string(),
+        });
+
+        Ok(())
+    }
+
+    /// Writes the index page linking every added game, sorted by game name
+    pub async fn finish(mut self) -> Result<()> {
+        self.games.sort_by_key(|game| game.name);
+
+        let mut body = String::from("<h1>Song database</h1>\n<table>\n<thead><tr>");
+        body.push_str("<th>Game</th><th>Songs</th><th>Last Updated</th></tr></thead>\n<tbody>\n");
+        for game in &self.games {
+            let _ = writeln!(
+                body,
+                "<tr><td><a href=\"{}.html\">{}</a></td><td>{}</td><td>{}</td></tr>",
+                escape(game.name),
+                escape(game.display_name),
+                game.songs,
+                escape(&game.last_updated),
+            );
+        }
+        body.push_str("</tbody>\n</table>\n");
+
+        let index_path = self.path.join("index.html");
+        info!("Writing index to {:?}", index_path.as_os_str());
+        tokio::fs::write(index_path, page("Song database", &body, false)).await?;
+
+        Ok(())
+    }
+
+    /// Copies the mirrored jackets of the game into the site, returning the relative path of
+    /// each song's jacket by song key
+    async fn copy_jackets<G>(&self, data_path: &Path) -> Result<HashMap<String, String>>
+    where
+        G: Otoge,
+    {
+        let manifest = JacketManifest::load(&G::jacket_manifest_path(Some(data_path))).await?;
+        if manifest.jackets.is_empty() {
+            return Ok(HashMap::new());
+        }
+
+        let source_dir = G::jackets_path(Some(data_path));
+        let target_dir = self.path.join("jackets").join(G::name());
+        tokio::fs::create_dir_all(&target_dir).await?;
+
+        let mut jackets = HashMap::new();
+        for (key, entry) in manifest.jackets {
+            let source = source_dir.join(&entry.file);
+            if !tokio::fs::try_exists(&source).await? {
+                continue;
+            }
+
+            // Files are named after their contents, an existing file is always up-to-date
+            let target = target_dir.join(&entry.file);
+            if !tokio::fs::try_exists(&target).await? {
+                tokio::fs::copy(&source, &target).await?;
+            }
+
+            jackets.insert(key, format!("jackets/{}/{}", G::name(), entry.file));
+        }
+
+        Ok(jackets)
+    }
+}
+
+fn render_game_page<G>(data_store: &G::DataStore, jackets: &HashMap<String, String>) -> String
+where
+    G: Otoge,
+    G::DataStore: DataStore,
+{
+    // Keeps the game's difficulty order but leaves out difficulties no available song has
+    let difficulties: Vec<&'static str> = data_store
+        .difficulties()
+        .into_iter()
+        .filter(|difficulty| {
+            data_store
+                .available_songs()
+                .any(|song| song.charts().iter().any(|(chart, _)| chart == difficulty))
+        })
+        .collect();
+    let show_jackets = !jackets.is_empty();
+
+    let mut categories: Vec<&str> = vec![];
+    for song in data_store.available_songs() {
+        for name in song.category_names() {
+            if !categories.contains(&name) {
+                categories.push(name);
+            }
+        }
+    }
+
+    let mut body = String::from("<p><a href=\"index.html\">All games</a></p>\n");
+    let _ = writeln!(
+        body,
+        "<h1>{}</h1>\n<p>Last updated {} &middot; <a href=\"{}\">Official site</a></p>",
+        escape(G::display_name()),
+        data_store.last_updated().format("%Y-%m-%d"),
+        escape(G::homepage()),
+    );
+
+    body.push_str("<div class=\"controls\">\n");
+    body.push_str("<input id=\"search\" type=\"search\" placeholder=\"Title or artist\">\n");
+    body.push_str("<select id=\"category\"><option value=\"\">All categories</option>");
+    for name in &categories {
+        let _ = write!(body, "<option>{}</option>", escape(name));
+    }
+    body.push_str("</select>\n<span id=\"count\" class=\"count\"></span>\n</div>\n");
+
+    body.push_str("<table class=\"songs\">\n<thead><tr>");
+    if show_jackets {
+        body.push_str("<th></th>");
+    }
+    body.push_str(
+        "<th data-sort=\"text\">Title</th><th data-sort=\"text\">Artist</th>\
+         <th data-sort=\"text\">Category</th>",
+    );
+    for difficulty in &difficulties {
+        let _ = write!(body, "<th data-sort=\"number\">{}</th>", escape(difficulty));
+    }
+    body.push_str("</tr></thead>\n<tbody>\n");
+
+    // Songs without a chart in the table, like WORLD'S END-only CHUNITHM songs, still get a
+    // row with empty level cells so they can be searched for
+    for song in data_store.available_songs() {
+        render_song_row(&mut body, song, &difficulties, show_jackets, jackets);
+    }
+    body.push_str("</tbody>\n</table>\n");
+
+    page(G::display_name(), &body, true)
+}
+
+fn render_song_row<S>(
+    body: &mut String,
+    song: &S,
+    difficulties: &[&'static str],
+    show_jackets: bool,
+    jackets: &HashMap<String, String>,
+) where
+    S: Song,
+{
+    let categories = song.category_names();
+    let search = [
+        song.title(),
+        song.artist(),
+        song.title_reading().unwrap_or_default(),
+    ]
+    .join("\n")
+    .to_lowercase();
+
+    let _ = write!(
+        body,
+        "<tr data-search=\"{}\" data-categories=\"{}\">",
+        escape(&search),
+        escape(&categories.join("\n")),
+    );
+
+    if show_jackets {
+        match jackets.get(song.key().as_ref()) {
+            Some(path) => {
+                let _ = write!(
+                    body,
+                    "<td class=\"jacket\"><img src=\"{}\" alt=\"\" loading=\"lazy\"></td>",
+                    escape(path),
+                );
+            }
+            None => body.push_str("<td class=\"jacket\"></td>"),
+        }
+    }
+
+    // Japanese titles sort by their reading when the game provides one
+    let _ = write!(
+        body,
+        "<td data-value=\"{}\">{}",
+        escape(song.title_reading().unwrap_or(song.title())),
+        escape(song.title()),
+    );
+    if let Some(reading) = song
+        .title_reading()
+        .filter(|reading| *reading != song.title())
+    {
+        let _ = write!(body, "<div class=\"reading\">{}</div>", escape(reading));
+    }
+    let _ = write!(
+        body,
+        "</td><td>{}</td><td>{}</td>",
+        escape(song.artist()),
+        escape(&categories.join(", ")),
+    );
+
+    let charts = song.charts();
+    for difficulty in difficulties {
+        match charts.iter().find(|(chart, _)| chart == difficulty) {
+            Some((_, level)) => {
+                let value = level.numeric().map(|value| value.to_string());
+                let _ = write!(
+                    body,
+                    "<td class=\"level\" data-value=\"{}\">{}</td>",
+                    value.unwrap_or_default(),
+                    escape(&level.to_string()),
+                );
+            }
+            None => body.push_str("<td class=\"level\" data-value=\"\"></td>"),
+        }
+    }
+
+    body.push_str("</tr>\n");
+}
+
+fn page(title: &str, body: &str, with_script: bool) -> String {
+    let script = if with_script {
+        "<script src=\"browser.js\"></script>\n"
+    } else {
+        ""
+    };
+
+    format!(
+        "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n\
+         <meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">\n\
+         <title>{}</title>\n<link rel=\"stylesheet\" href=\"style.css\">\n</head>\n\
+         <body>\n{body}{script}</body>\n</html>\n",
+        escape(title),
+    )
+}
+
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use otoge::chunithm::ChunithmJP;
+
+    use super::*;
+
+    const DATA_STORE: &str = r#"
+name = "chunithm_jp"
+count = 2
+last_updated = "2026-08-20T03:38:19Z"
+
+[[songs]]
+id = "1"
+title = "<Hello> \"World\""
+title_reading = "HELLOWORLD"
+artist = "A & B"
+image_id = "1.jpg"
+category = "ORIGINAL"
+is_new = false
+
+[songs.levels]
+basic = "3"
+master = "14+"
+
+[[songs]]
+id = "8328"
+title = "Flap&Clap"
+title_reading = "FLAPCLAP"
+artist = "棗いつき"
+image_id = "79d1a8a4e1271969.jpg"
+category = "ORIGINAL"
+is_new = false
+
+[songs.worlds_end]
+kanji = "跳"
+star = "7"
+
+[[categories]]
+slug = "original"
+name = "ORIGINAL"
+"#;
+
+    /// Song rows span several lines, the search text is newline separated
+    fn rows(page: &str) -> Vec<&str> {
+        page.split("<tr data-search")
+            .skip(1)
+            .map(|row| &row[..row.find("</tr>").unwrap()])
+            .collect()
+    }
+
+    #[test]
+    fn escapes_markup() {
+        assert_eq!(
+            escape(r#"<a href="x">Tom & Jerry's</a>"#),
+            "&lt;a href=&quot;x&quot;&gt;Tom &amp; Jerry&#39;s&lt;/a&gt;"
+        );
+        assert_eq!(escape("ミラクル☆"), "ミラクル☆");
+    }
+
+    #[test]
+    fn renders_a_row_per_song() {
+        let data_store = toml::from_str(DATA_STORE).unwrap();
+        let jackets = HashMap::from([(
+            "8328".to_owned(),
+            "jackets/chunithm_jp/79d1a8a4e1271969.jpg".to_owned(),
+        )]);
+        let page = render_game_page::<ChunithmJP>(&data_store, &jackets);
+
+        assert!(page.contains("&lt;Hello&gt; &quot;World&quot;"));
+        assert!(!page.contains("<Hello>"));
+
+        let header = page
+            .lines()
+            .find(|line| line.starts_with("<thead>"))
+            .unwrap();
+        let columns = header.matches("</th>").count();
+        // Jacket, title, artist, category and the two charted difficulties
+        assert_eq!(columns, 6);
+
+        let rows = rows(&page);
+        assert_eq!(rows.len(), 2);
+        for row in &rows {
+            assert_eq!(row.matches("</td>").count(), columns);
+        }
+
+        // No mirrored jacket, the cell is kept so the columns line up
+        assert!(rows[0].contains("<td class=\"jacket\"></td>"));
+        assert!(rows[1].contains("<img src=\"jackets/chunithm_jp/79d1a8a4e1271969.jpg\""));
+
+        assert!(rows[0].contains("<td class=\"level\" data-value=\"14.5\">14+</td>"));
+        assert_eq!(
+            rows[1]
+                .matches("<td class=\"level\" data-value=\"\"></td>")
+                .count(),
+            2
+        );
+    }
+}
@@ -0,0 +1,67 @@
+"use strict";
+
+const table = document.querySelector("table.songs");
+const rows = Array.from(table.tBodies[0].rows);
+const search = document.getElementById("search");
+const category = document.getElementById("category");
+const count = document.getElementById("count");
+
+function applyFilter() {
+    const query = search.value.trim().toLowerCase();
+    const selected = category.value;
+    let shown = 0;
+
+    for (const row of rows) {
+        const matches =
+            row.dataset.search.includes(query) &&
+            (selected === "" || row.dataset.categories.split("\n").includes(selected));
+        row.hidden = !matches;
+        if (matches) {
+            shown += 1;
+        }
+    }
+
+    count.textContent = `${shown} / ${rows.length} songs`;
+}
+
+function sortValue(row, column, numeric) {
+    const cell = row.cells[column];
+    if (numeric) {
+        // Charts without a level always go last
+        return cell.dataset.value === "" ? null : Number(cell.dataset.value);
+    }
+    return cell.dataset.value ?? cell.textContent;
+}
+
+function sortBy(header) {
+    const column = header.cellIndex;
+    const numeric = header.dataset.sort === "number";
+    const direction = header.getAttribute("aria-sort") === "ascending" ? -1 : 1;
+    const collator = new Intl.Collator(undefined, { numeric: true });
+
+    for (const other of table.tHead.rows[0].cells) {
+        other.removeAttribute("aria-sort");
+    }
+    header.setAttribute("aria-sort", direction === 1 ? "ascending" : "descending");
+
+    const sorted = rows.slice().sort((a, b) => {
+        const left = sortValue(a, column, numeric);
+        const right = sortValue(b, column, numeric);
+        if (left === null || right === null) {
+            return (left === null) - (right === null);
+        }
+        return direction * (numeric ? left - right : collator.compare(left, right));
+    });
+
+    table.tBodies[0].append(...sorted);
+}
+
+for (const header of table.tHead.rows[0].cells) {
+    if (header.dataset.sort) {
+        header.addEventListener("click", () => sortBy(header));
+    }
+}
+
+search.addEventListener("input", applyFilter);
+category.addEventListener("change", applyFilter);
+applyFilter();
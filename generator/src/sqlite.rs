@@ -0,0 +1,206 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+use anyhow::Result;
+use otoge::shared::traits::{DataStore, Otoge, Song};
+use rusqlite::{Connection, Transaction, params};
+
+const SCHEMA: &str = "
+CREATE TABLE games (
+    name TEXT PRIMARY KEY,
+    display_name TEXT NOT NULL,
+    homepage TEXT NOT NULL,
+    last_updated TEXT NOT NULL
+);
+
+CREATE TABLE songs (
+    id INTEGER PRIMARY KEY,
+    game TEXT NOT NULL REFERENCES games (name),
+    key TEXT NOT NULL,
+    title TEXT NOT NULL,
+    title_reading TEXT,
+    artist TEXT NOT NULL,
+    release_date TEXT,
+    first_seen TEXT,
+    removed_at TEXT,
+    UNIQUE (game, key)
+);
+
+CREATE TABLE charts (
+    song_id INTEGER NOT NULL REFERENCES songs (id),
+    difficulty TEXT NOT NULL,
+    level TEXT NOT NULL,
+    -- NULL for undisclosed levels, + counts as half a level
+    level_value REAL,
+    PRIMARY KEY (song_id, difficulty)
+);
+
+CREATE TABLE categories (
+    id INTEGER PRIMARY KEY,
+    game TEXT NOT NULL REFERENCES games (name),
+    name TEXT NOT NULL,
+    UNIQUE (game, name)
+);
+
+CREATE TABLE song_categories (
+    song_id INTEGER NOT NULL REFERENCES songs (id),
+    category_id INTEGER NOT NULL REFERENCES categories (id),
+    PRIMARY KEY (song_id, category_id)
+);
+
+CREATE INDEX songs_title ON songs (title);
+CREATE INDEX charts_level_value ON charts (level_value);
+CREATE INDEX charts_difficulty_level_value ON charts (difficulty, level_value);
+CREATE INDEX song_categories_category ON song_categories (category_id);
+";
+
+/// Writes every game into a fresh SQLite database, replacing the file if it exists
+pub struct SqliteExport {
+    connection: Connection,
+}
+
+impl SqliteExport {
+    pub fn create(path: &Path) -> Result<Self> {
+        if path.exists() {
+            std::fs::remove_file(path)?;
+        }
+
+        Self::with_connection(Connection::open(path)?)
+    }
+
+    fn with_connection(connection: Connection) -> Result<Self> {
+        connection.execute_batch(SCHEMA)?;
+
+        Ok(Self { connection })
+    }
+
+    pub fn insert<G>(&mut self, data_store: &G::DataStore) -> Result<()>
+    where
+        G: Otoge,
+        G::DataStore: DataStore,
+    {
+        let transaction = self.connection.transaction()?;
+
+        transaction.execute(
+            "INSERT INTO games (name, display_name, homepage, last_updated)
+             VALUES (?1, ?2, ?3, ?4)",
+            params![
+                G::name(),
+                G::display_name(),
+                G::homepage(),
+                data_store.last_updated().to_rfc3339()
+            ],
+        )?;
+
+        let mut category_ids = HashMap::new();
+        for song in data_store.songs() {
+            insert_song(&transaction, G::name(), song, &mut category_ids)?;
+        }
+
+        transaction.commit()?;
+        Ok(())
+    }
+}
+
+fn insert_song<S>(
+    transaction: &Transaction<'_>,
+    game: &str,
+    song: &S,
+    category_ids: &mut HashMap<String, i64>,
+) -> Result<()>
+where
+    S: Song,
+{
+    transaction.execute(
+        "INSERT INTO songs
+             (game, key, title, title_reading, artist, release_date, first_seen, removed_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+        params![
+            game,
+            song.key(),
+            song.title(),
+            song.title_reading(),
+            song.artist(),
+            song.release_date().map(|date| date.to_string()),
+            song.first_seen().map(|date| date.to_string()),
+            song.removed_at().map(|date| date.to_string()),
+        ],
+    )?;
+    let song_id = transaction.last_insert_rowid();
+
+    let mut insert_chart = transaction.prepare_cached(
+        "INSERT INTO charts (song_id, difficulty, level, level_value) VALUES (?1, ?2, ?3, ?4)",
+    )?;
+    for (difficulty, level) in song.charts() {
+        insert_chart.execute(params![
+            song_id,
+            difficulty,
+            level.to_string(),
+            level.numeric()
+        ])?;
+    }
+
+    let mut insert_category =
+        transaction.prepare_cached("INSERT INTO categories (game, name) VALUES (?1, ?2)")?;
+    let mut insert_song_category = transaction.prepare_cached(
+        "INSERT OR IGNORE INTO song_categories (song_id, category_id) VALUES (?1, ?2)",
+    )?;
+    for name in song.category_names() {
+        let category_id = match category_ids.get(name) {
+            Some(category_id) => *category_id,
+            None => {
+                insert_category.execute(params![game, name])?;
+                let category_id = transaction.last_insert_rowid();
+                category_ids.insert(name.to_owned(), category_id);
+                category_id
+            }
+        };
+
+        insert_song_category.execute(params![song_id, category_id])?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use otoge::chunithm::ChunithmJP;
+    use otoge::helpers::load_local_data_store;
+    use otoge::shared::level::Level;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn finds_chunithm_charts_by_level_and_category() -> Result<()> {
+        let data_path = Path::new(env!("CARGO_MANIFEST_DIR")).join("../data");
+        let data_store = load_local_data_store::<ChunithmJP>(Some(&data_path))
+            .await?
+            .expect("CHUNITHM data should be checked in");
+
+        let mut export = SqliteExport::with_connection(Connection::open_in_memory()?)?;
+        export.insert::<ChunithmJP>(&data_store)?;
+
+        let rows: i64 = export.connection.query_row(
+            "SELECT COUNT(*) FROM charts
+             JOIN songs ON songs.id = charts.song_id
+             JOIN song_categories ON song_categories.song_id = songs.id
+             JOIN categories ON categories.id = song_categories.category_id
+             WHERE songs.game = 'chunithm_jp' AND songs.removed_at IS NULL
+               AND categories.name = 'ORIGINAL' AND charts.level_value = 14.5",
+            [],
+            |row| row.get(0),
+        )?;
+
+        let fourteen_plus: Level = "14+".parse()?;
+        let expected = data_store
+            .available_songs()
+            .filter(|song| song.category_names().contains(&"ORIGINAL"))
+            .flat_map(|song| song.charts())
+            .filter(|(_, level)| level.numeric() == fourteen_plus.numeric())
+            .count();
+
+        assert!(expected > 0);
+        assert_eq!(usize::try_from(rows)?, expected);
+        Ok(())
+    }
+}
@@ -1,7 +0,0 @@
-use otoge::shared::traits::Otoge;
-
-pub trait GenerateTask<G>
-where
-    G: Otoge,
-{
-}
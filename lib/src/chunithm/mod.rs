@@ -13,6 +13,18 @@ impl Otoge for ChunithmJP {
     fn name() -> &'static str {
         "chunithm_jp"
     }
+
+    fn display_name() -> &'static str {
+        "CHUNITHM (Japan)"
+    }
+
+    fn homepage() -> &'static str {
+        "https://chunithm.sega.jp/"
+    }
+
+    fn jacket_base_url() -> Option<&'static str> {
+        Some("https://new.chunithm-net.com/chuni-mobile/html/mobile/img/")
+    }
 }
 
 pub struct ChunithmIntl;
@@ -23,6 +35,18 @@ impl Otoge for ChunithmIntl {
     fn name() -> &'static str {
         "chunithm_intl"
     }
+
+    fn display_name() -> &'static str {
+        "CHUNITHM (International)"
+    }
+
+    fn homepage() -> &'static str {
+        "https://chunithm.sega.com/"
+    }
+
+    fn jacket_base_url() -> Option<&'static str> {
+        Some("https://chunithm-net-eng.com/mobile/img/")
+    }
 }
 
 pub(crate) fn get_all_categories() -> Vec<Category> {
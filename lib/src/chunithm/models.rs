@@ -1,15 +1,17 @@
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, NaiveDate, Utc};
 use serde::{Deserialize, Serialize};
 use std::borrow::Cow;
 
 use crate::shared::deserializers::{
     all_default_values_as_none, bool_from_string, empty_string_as_none,
 };
-use crate::shared::traits::DataStore as DataStoreTrait;
+use crate::shared::diff::SongFields;
+use crate::shared::level::Level;
+use crate::shared::traits::{DataStore as DataStoreTrait, Song as SongTrait, SongFlag};
 
 use super::get_all_categories;
 
-#[derive(Serialize, Deserialize, Debug, Default, PartialEq)]
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
 #[allow(dead_code)]
 pub struct LevelMap {
     #[serde(deserialize_with = "empty_string_as_none")]
@@ -17,34 +19,53 @@ pub struct LevelMap {
     // FIXME: Be more explicit when deserializing API
     #[serde(alias = "lev_bas")]
     #[serde(default = "Option::default")]
-    basic: Option<String>,
+    basic: Option<Level>,
     #[serde(deserialize_with = "empty_string_as_none")]
     #[serde(skip_serializing_if = "Option::is_none")]
     // FIXME: Be more explicit when deserializing API
     #[serde(alias = "lev_adv")]
     #[serde(default = "Option::default")]
-    advanced: Option<String>,
+    advanced: Option<Level>,
     #[serde(deserialize_with = "empty_string_as_none")]
     #[serde(skip_serializing_if = "Option::is_none")]
     // FIXME: Be more explicit when deserializing API
     #[serde(alias = "lev_exp")]
     #[serde(default = "Option::default")]
-    expert: Option<String>,
+    expert: Option<Level>,
     #[serde(deserialize_with = "empty_string_as_none")]
     #[serde(skip_serializing_if = "Option::is_none")]
     // FIXME: Be more explicit when deserializing API
     #[serde(alias = "lev_mas")]
     #[serde(default = "Option::default")]
-    master: Option<String>,
+    master: Option<Level>,
     #[serde(deserialize_with = "empty_string_as_none")]
     #[serde(skip_serializing_if = "Option::is_none")]
     // FIXME: Be more explicit when deserializing API
     #[serde(alias = "lev_ult")]
     #[serde(default = "Option::default")]
-    ultima: Option<String>,
+    ultima: Option<Level>,
 }
 
-#[derive(Serialize, Deserialize, Debug, Default, PartialEq)]
+impl LevelMap {
+    /// Difficulty names in the order the game lists them
+    pub const DIFFICULTIES: [&'static str; 5] = ["basic", "advanced", "expert", "master", "ultima"];
+
+    pub fn charts(&self) -> Vec<(&'static str, Level)> {
+        Self::DIFFICULTIES
+            .into_iter()
+            .zip([
+                self.basic,
+                self.advanced,
+                self.expert,
+                self.master,
+                self.ultima,
+            ])
+            .filter_map(|(difficulty, level)| level.map(|level| (difficulty, level)))
+            .collect()
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
 #[allow(dead_code)]
 pub struct WorldsEndInfo {
     #[serde(alias = "we_kanji")]
@@ -80,7 +101,7 @@ pub struct SongFromAPI {
     worlds_end: Option<WorldsEndInfo>,
 }
 
-#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 #[allow(dead_code)]
 pub struct Song {
     id: String,
@@ -91,6 +112,10 @@ pub struct Song {
     pub category: String,
 
     is_new: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    first_seen: Option<NaiveDate>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    removed_at: Option<NaiveDate>,
 
     #[serde(skip_serializing_if = "Option::is_none")]
     levels: Option<LevelMap>,
@@ -111,11 +136,91 @@ impl From<SongFromAPI> for Song {
             is_new: other.is_new,
             levels: other.levels,
             worlds_end: other.worlds_end,
+            first_seen: None,
+            removed_at: None,
+        }
+    }
+}
+
+impl SongTrait for Song {
+    const DIFFICULTIES: &'static [&'static str] = &LevelMap::DIFFICULTIES;
+
+    fn key(&self) -> Cow<'_, str> {
+        Cow::Borrowed(&self.id)
+    }
+
+    fn title(&self) -> &str {
+        &self.title
+    }
+
+    fn artist(&self) -> &str {
+        &self.artist
+    }
+
+    fn image_id(&self) -> Option<&str> {
+        Some(&self.image_id)
+    }
+
+    fn title_reading(&self) -> Option<&str> {
+        Some(&self.title_reading)
+    }
+
+    fn categories(&self) -> Vec<&str> {
+        vec![&self.category]
+    }
+
+    fn category_names(&self) -> Vec<&str> {
+        vec![&self.category]
+    }
+
+    fn charts(&self) -> Vec<(&'static str, Level)> {
+        self.levels.iter().flat_map(LevelMap::charts).collect()
+    }
+
+    fn has_flag(&self, flag: SongFlag) -> bool {
+        match flag {
+            SongFlag::New => self.is_new,
+            _ => false,
         }
     }
+
+    fn fields(&self) -> SongFields {
+        SongFields::default()
+            .field("title", &self.title)
+            .field("title_reading", &self.title_reading)
+            .field("artist", &self.artist)
+            .field("image_id", &self.image_id)
+            .field("category", &self.category)
+            .field("is_new", self.is_new)
+            .levels("levels", self.levels.iter().flat_map(LevelMap::charts))
+            .optional_field(
+                "worlds_end.kanji",
+                self.worlds_end.as_ref().map(|we| &we.kanji),
+            )
+            .optional_field(
+                "worlds_end.star",
+                self.worlds_end.as_ref().map(|we| &we.star),
+            )
+    }
+
+    fn first_seen(&self) -> Option<NaiveDate> {
+        self.first_seen
+    }
+
+    fn removed_at(&self) -> Option<NaiveDate> {
+        self.removed_at
+    }
+
+    fn set_first_seen(&mut self, date: Option<NaiveDate>) {
+        self.first_seen = date;
+    }
+
+    fn set_removed_at(&mut self, date: Option<NaiveDate>) {
+        self.removed_at = date;
+    }
 }
 
-#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct Category {
     pub slug: Cow<'static, str>,
     pub name: Cow<'static, str>,
@@ -134,7 +239,7 @@ impl DataStore {
     pub fn new(name: &'static str, songs: Vec<Song>) -> Self {
         Self {
             name: Cow::Borrowed(name),
-            count: songs.len(),
+            count: songs.iter().filter(|song| !song.is_removed()).count(),
             songs,
             last_updated: Utc::now(),
             categories: get_all_categories(),
@@ -143,9 +248,21 @@ impl DataStore {
 }
 
 impl DataStoreTrait for DataStore {
-    fn data_differs(&self, other: &Self) -> bool {
-        self.count != other.count
-            || !self.songs.iter().eq(other.songs.iter())
-            || !self.categories.iter().eq(other.categories.iter())
+    type Song = Song;
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn songs(&self) -> &[Song] {
+        &self.songs
+    }
+
+    fn last_updated(&self) -> DateTime<Utc> {
+        self.last_updated
+    }
+
+    fn definitions_differ(&self, other: &Self) -> bool {
+        !self.categories.iter().eq(other.categories.iter())
     }
 }
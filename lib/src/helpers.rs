@@ -2,6 +2,7 @@ use anyhow::Result;
 use std::path::Path;
 use tracing::info;
 
+use crate::shared::changelog::Changelog;
 use crate::shared::traits::{DataStore, Otoge};
 
 pub async fn load_local_data_store<G>(
@@ -22,6 +23,17 @@ where
     Ok(local_data_store)
 }
 
+pub async fn load_changelog<G>(base_path: Option<&Path>) -> Result<Changelog>
+where
+    G: Otoge,
+{
+    let changelog_path = G::changelog_path(base_path);
+
+    info!("Loading changelog at {:?}", changelog_path.as_os_str());
+
+    Changelog::load(changelog_path.as_path()).await
+}
+
 async fn read_music_toml<S>(file_path: &Path) -> Result<S>
 where
     S: serde::de::DeserializeOwned,
@@ -2,7 +2,8 @@ mod deserializers;
 pub mod models;
 
 use crate::shared::traits::Otoge;
-use models::Category;
+use chrono::NaiveDate;
+use models::{Category, Version};
 
 use std::borrow::Cow;
 
@@ -14,6 +15,18 @@ impl Otoge for MaimaiJP {
     fn name() -> &'static str {
         "maimai_jp"
     }
+
+    fn display_name() -> &'static str {
+        "maimai DX (Japan)"
+    }
+
+    fn homepage() -> &'static str {
+        "https://maimai.sega.jp/"
+    }
+
+    fn jacket_base_url() -> Option<&'static str> {
+        Some("https://maimai.sega.jp/storage/DX_jacket/")
+    }
 }
 
 pub struct MaimaiIntl;
@@ -24,6 +37,18 @@ impl Otoge for MaimaiIntl {
     fn name() -> &'static str {
         "maimai_intl"
     }
+
+    fn display_name() -> &'static str {
+        "maimai DX (International)"
+    }
+
+    fn homepage() -> &'static str {
+        "https://maimai.sega.com/"
+    }
+
+    fn jacket_base_url() -> Option<&'static str> {
+        Some("https://maimai.sega.com/assets/img/download/jacket/")
+    }
 }
 
 pub fn get_all_jp_categories() -> Vec<Category> {
@@ -91,3 +116,173 @@ pub fn get_all_intl_categories() -> Vec<Category> {
         },
     ]
 }
+
+/// Every version in release order, from the original maimai up to the latest でらっくす update.
+/// Versions before でらっくす were never released on international cabinets, their songs only
+/// carried over.
+pub fn get_all_versions() -> Vec<Version> {
+    vec![
+        Version {
+            code: Cow::Borrowed("10000"),
+            name: Cow::Borrowed("maimai"),
+            jp_release: NaiveDate::from_ymd_opt(2012, 7, 11),
+            intl: false,
+        },
+        Version {
+            code: Cow::Borrowed("11000"),
+            name: Cow::Borrowed("maimai PLUS"),
+            jp_release: NaiveDate::from_ymd_opt(2012, 12, 13),
+            intl: false,
+        },
+        Version {
+            code: Cow::Borrowed("12000"),
+            name: Cow::Borrowed("maimai GreeN"),
+            jp_release: NaiveDate::from_ymd_opt(2013, 7, 11),
+            intl: false,
+        },
+        Version {
+            code: Cow::Borrowed("13000"),
+            name: Cow::Borrowed("maimai GreeN PLUS"),
+            jp_release: NaiveDate::from_ymd_opt(2014, 2, 26),
+            intl: false,
+        },
+        Version {
+            code: Cow::Borrowed("14000"),
+            name: Cow::Borrowed("maimai ORANGE"),
+            jp_release: NaiveDate::from_ymd_opt(2014, 9, 18),
+            intl: false,
+        },
+        Version {
+            code: Cow::Borrowed("15000"),
+            name: Cow::Borrowed("maimai ORANGE PLUS"),
+            jp_release: NaiveDate::from_ymd_opt(2015, 3, 19),
+            intl: false,
+        },
+        Version {
+            code: Cow::Borrowed("16000"),
+            name: Cow::Borrowed("maimai PiNK"),
+            jp_release: NaiveDate::from_ymd_opt(2015, 12, 9),
+            intl: false,
+        },
+        Version {
+            code: Cow::Borrowed("17000"),
+            name: Cow::Borrowed("maimai PiNK PLUS"),
+            jp_release: NaiveDate::from_ymd_opt(2016, 6, 30),
+            intl: false,
+        },
+        Version {
+            code: Cow::Borrowed("18000"),
+            name: Cow::Borrowed("maimai MURASAKi"),
+            jp_release: NaiveDate::from_ymd_opt(2016, 12, 14),
+            intl: false,
+        },
+        Version {
+            code: Cow::Borrowed("18500"),
+            name: Cow::Borrowed("maimai MURASAKi PLUS"),
+            jp_release: NaiveDate::from_ymd_opt(2017, 6, 22),
+            intl: false,
+        },
+        Version {
+            code: Cow::Borrowed("19000"),
+            name: Cow::Borrowed("maimai MiLK"),
+            jp_release: NaiveDate::from_ymd_opt(2017, 12, 14),
+            intl: false,
+        },
+        Version {
+            code: Cow::Borrowed("19500"),
+            name: Cow::Borrowed("maimai MiLK PLUS"),
+            jp_release: NaiveDate::from_ymd_opt(2018, 6, 21),
+            intl: false,
+        },
+        Version {
+            code: Cow::Borrowed("19900"),
+            name: Cow::Borrowed("maimai FiNALE"),
+            jp_release: NaiveDate::from_ymd_opt(2018, 12, 13),
+            intl: false,
+        },
+        Version {
+            code: Cow::Borrowed("20000"),
+            name: Cow::Borrowed("maimaiでらっくす"),
+            jp_release: NaiveDate::from_ymd_opt(2019, 7, 11),
+            intl: true,
+        },
+        Version {
+            code: Cow::Borrowed("20500"),
+            name: Cow::Borrowed("maimaiでらっくす PLUS"),
+            jp_release: NaiveDate::from_ymd_opt(2020, 1, 23),
+            intl: true,
+        },
+        Version {
+            code: Cow::Borrowed("21000"),
+            name: Cow::Borrowed("maimaiでらっくす Splash"),
+            jp_release: NaiveDate::from_ymd_opt(2020, 9, 17),
+            intl: true,
+        },
+        Version {
+            code: Cow::Borrowed("21500"),
+            name: Cow::Borrowed("maimaiでらっくす Splash PLUS"),
+            jp_release: NaiveDate::from_ymd_opt(2021, 3, 18),
+            intl: true,
+        },
+        Version {
+            code: Cow::Borrowed("22000"),
+            name: Cow::Borrowed("maimaiでらっくす UNiVERSE"),
+            jp_release: NaiveDate::from_ymd_opt(2021, 9, 16),
+            intl: true,
+        },
+        Version {
+            code: Cow::Borrowed("22500"),
+            name: Cow::Borrowed("maimaiでらっくす UNiVERSE PLUS"),
+            jp_release: NaiveDate::from_ymd_opt(2022, 3, 24),
+            intl: true,
+        },
+        Version {
+            code: Cow::Borrowed("23000"),
+            name: Cow::Borrowed("maimaiでらっくす FESTiVAL"),
+            jp_release: NaiveDate::from_ymd_opt(2022, 9, 15),
+            intl: true,
+        },
+        Version {
+            code: Cow::Borrowed("23500"),
+            name: Cow::Borrowed("maimaiでらっくす FESTiVAL PLUS"),
+            jp_release: NaiveDate::from_ymd_opt(2023, 3, 23),
+            intl: true,
+        },
+        Version {
+            code: Cow::Borrowed("24000"),
+            name: Cow::Borrowed("maimaiでらっくす BUDDiES"),
+            jp_release: NaiveDate::from_ymd_opt(2023, 9, 14),
+            intl: true,
+        },
+        Version {
+            code: Cow::Borrowed("24500"),
+            name: Cow::Borrowed("maimaiでらっくす BUDDiES PLUS"),
+            jp_release: NaiveDate::from_ymd_opt(2024, 3, 21),
+            intl: true,
+        },
+        Version {
+            code: Cow::Borrowed("25000"),
+            name: Cow::Borrowed("maimaiでらっくす PRiSM"),
+            jp_release: NaiveDate::from_ymd_opt(2024, 9, 12),
+            intl: true,
+        },
+        Version {
+            code: Cow::Borrowed("25500"),
+            name: Cow::Borrowed("maimaiでらっくす PRiSM PLUS"),
+            jp_release: NaiveDate::from_ymd_opt(2025, 3, 13),
+            intl: true,
+        },
+        Version {
+            code: Cow::Borrowed("26000"),
+            name: Cow::Borrowed("maimaiでらっくす CiRCLE"),
+            jp_release: NaiveDate::from_ymd_opt(2025, 9, 18),
+            intl: true,
+        },
+        Version {
+            code: Cow::Borrowed("26500"),
+            name: Cow::Borrowed("maimaiでらっくす CiRCLE PLUS"),
+            jp_release: NaiveDate::from_ymd_opt(2026, 3, 19),
+            intl: true,
+        },
+    ]
+}
@@ -1,15 +1,19 @@
 use chrono::{DateTime, NaiveDate, Utc};
 use serde::{Deserialize, Serialize};
 use std::borrow::Cow;
+use std::sync::LazyLock;
 
 use crate::shared::deserializers::{
     all_default_values_as_none, bool_from_option_string, empty_string_as_none,
 };
-use crate::shared::traits::DataStore as DataStoreTrait;
+use crate::shared::diff::SongFields;
+use crate::shared::level::Level;
+use crate::shared::traits::{DataStore as DataStoreTrait, Song as SongTrait, SongFlag};
 
 use super::deserializers::deserialize_date;
+use super::get_all_versions;
 
-#[derive(Serialize, Deserialize, Debug, Default, PartialEq)]
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
 #[allow(dead_code)]
 pub struct LevelMap {
     #[serde(deserialize_with = "empty_string_as_none")]
@@ -17,34 +21,54 @@ pub struct LevelMap {
     // FIXME: Be more explicit when deserializing API
     #[serde(alias = "lev_bas")]
     #[serde(default = "Option::default")]
-    basic: Option<String>,
+    basic: Option<Level>,
     #[serde(deserialize_with = "empty_string_as_none")]
     #[serde(skip_serializing_if = "Option::is_none")]
     // FIXME: Be more explicit when deserializing API
     #[serde(alias = "lev_adv")]
     #[serde(default = "Option::default")]
-    advanced: Option<String>,
+    advanced: Option<Level>,
     #[serde(deserialize_with = "empty_string_as_none")]
     #[serde(skip_serializing_if = "Option::is_none")]
     // FIXME: Be more explicit when deserializing API
     #[serde(alias = "lev_exp")]
     #[serde(default = "Option::default")]
-    expert: Option<String>,
+    expert: Option<Level>,
     #[serde(deserialize_with = "empty_string_as_none")]
     #[serde(skip_serializing_if = "Option::is_none")]
     // FIXME: Be more explicit when deserializing API
     #[serde(alias = "lev_mas")]
     #[serde(default = "Option::default")]
-    master: Option<String>,
+    master: Option<Level>,
     #[serde(deserialize_with = "empty_string_as_none")]
     #[serde(skip_serializing_if = "Option::is_none")]
     // FIXME: Be more explicit when deserializing API
     #[serde(alias = "lev_remas")]
     #[serde(default = "Option::default")]
-    remaster: Option<String>,
+    remaster: Option<Level>,
 }
 
-#[derive(Serialize, Deserialize, Debug, Default, PartialEq)]
+impl LevelMap {
+    /// Difficulty names in the order the game lists them
+    pub const DIFFICULTIES: [&'static str; 5] =
+        ["basic", "advanced", "expert", "master", "remaster"];
+
+    pub fn charts(&self) -> Vec<(&'static str, Level)> {
+        Self::DIFFICULTIES
+            .into_iter()
+            .zip([
+                self.basic,
+                self.advanced,
+                self.expert,
+                self.master,
+                self.remaster,
+            ])
+            .filter_map(|(difficulty, level)| level.map(|level| (difficulty, level)))
+            .collect()
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
 #[allow(dead_code)]
 pub struct DXLevelMap {
     #[serde(deserialize_with = "empty_string_as_none")]
@@ -52,40 +76,110 @@ pub struct DXLevelMap {
     // FIXME: Be more explicit when deserializing API
     #[serde(alias = "dx_lev_bas")]
     #[serde(default = "Option::default")]
-    basic: Option<String>,
+    basic: Option<Level>,
     #[serde(deserialize_with = "empty_string_as_none")]
     #[serde(skip_serializing_if = "Option::is_none")]
     // FIXME: Be more explicit when deserializing API
     #[serde(alias = "dx_lev_adv")]
     #[serde(default = "Option::default")]
-    advanced: Option<String>,
+    advanced: Option<Level>,
     #[serde(deserialize_with = "empty_string_as_none")]
     #[serde(skip_serializing_if = "Option::is_none")]
     // FIXME: Be more explicit when deserializing API
     #[serde(alias = "dx_lev_exp")]
     #[serde(default = "Option::default")]
-    expert: Option<String>,
+    expert: Option<Level>,
     #[serde(deserialize_with = "empty_string_as_none")]
     #[serde(skip_serializing_if = "Option::is_none")]
     // FIXME: Be more explicit when deserializing API
     #[serde(alias = "dx_lev_mas")]
     #[serde(default = "Option::default")]
-    master: Option<String>,
+    master: Option<Level>,
     #[serde(deserialize_with = "empty_string_as_none")]
     #[serde(skip_serializing_if = "Option::is_none")]
     // FIXME: Be more explicit when deserializing API
     #[serde(alias = "dx_lev_remas")]
     #[serde(default = "Option::default")]
-    remaster: Option<String>,
+    remaster: Option<Level>,
 }
 
-#[derive(Serialize, Deserialize, Debug, Default, PartialEq)]
-#[allow(dead_code)]
-pub struct Utage {
-    #[serde(alias = "lev_utage")]
-    level: String,
+impl DXLevelMap {
+    /// Difficulty names in the order the game lists them
+    pub const DIFFICULTIES: [&'static str; 5] =
+        ["basic", "advanced", "expert", "master", "remaster"];
+
+    pub fn charts(&self) -> Vec<(&'static str, Level)> {
+        Self::DIFFICULTIES
+            .into_iter()
+            .zip([
+                self.basic,
+                self.advanced,
+                self.expert,
+                self.master,
+                self.remaster,
+            ])
+            .filter_map(|(difficulty, level)| level.map(|level| (difficulty, level)))
+            .collect()
+    }
+}
+
+#[derive(Deserialize, Debug)]
+struct UtageFromAPI {
+    #[serde(rename(deserialize = "lev_utage"))]
+    level: Level,
     kanji: String,
     comment: String,
+    /// Only split per player on some buddy charts
+    #[serde(rename(deserialize = "lev_utage_left"))]
+    #[serde(deserialize_with = "empty_string_as_none")]
+    #[serde(default = "Option::default")]
+    left_level: Option<Level>,
+    #[serde(rename(deserialize = "lev_utage_right"))]
+    #[serde(deserialize_with = "empty_string_as_none")]
+    #[serde(default = "Option::default")]
+    right_level: Option<Level>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Utage {
+    pub level: Level,
+    /// Single character shown in brackets before the title, hinting at the gimmick
+    pub kanji: String,
+    pub comment: String,
+    /// Set on two-player charts, played by two people on neighbouring cabinets
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub buddy: Option<BuddyLevels>,
+}
+
+impl Utage {
+    fn from_api(utage: UtageFromAPI, buddy: Option<&str>) -> Self {
+        // Any non-empty marker counts, so an unexpected value can't fail the whole fetch
+        let buddy = buddy
+            .is_some_and(|buddy| !buddy.trim().is_empty())
+            .then(|| BuddyLevels {
+                left: utage.left_level.unwrap_or(utage.level),
+                right: utage.right_level.unwrap_or(utage.level),
+            });
+
+        Self {
+            level: utage.level,
+            kanji: utage.kanji,
+            comment: utage.comment,
+            buddy,
+        }
+    }
+
+    pub fn is_buddy(&self) -> bool {
+        self.buddy.is_some()
+    }
+}
+
+/// Levels of each player's half of a buddy chart, the API only lists the overall level
+/// for most charts in which case both halves share it
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct BuddyLevels {
+    pub left: Level,
+    pub right: Level,
 }
 
 #[derive(Deserialize, Debug)]
@@ -126,10 +220,10 @@ pub struct SongFromAPI {
     dx_levels: Option<DXLevelMap>,
 
     #[serde(flatten)]
-    utage: Option<Utage>,
+    utage: Option<UtageFromAPI>,
 }
 
-#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 #[allow(dead_code)]
 pub struct Song {
     id: String,
@@ -143,6 +237,10 @@ pub struct Song {
     release: Option<NaiveDate>,
     is_new: bool,
     is_locked: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    first_seen: Option<NaiveDate>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    removed_at: Option<NaiveDate>,
 
     #[serde(skip_serializing_if = "Option::is_none")]
     levels: Option<LevelMap>,
@@ -154,6 +252,17 @@ pub struct Song {
     utage: Option<Utage>,
 }
 
+impl Song {
+    /// Code of the update the song was added in, see [`DataStore::version_of`]
+    pub fn version(&self) -> &str {
+        &self.version
+    }
+
+    pub fn utage(&self) -> Option<&Utage> {
+        self.utage.as_ref()
+    }
+}
+
 impl From<SongFromAPI> for Song {
     fn from(other: SongFromAPI) -> Song {
         Self {
@@ -169,17 +278,184 @@ impl From<SongFromAPI> for Song {
             release: other.release,
             levels: other.levels,
             dx_levels: other.dx_levels,
-            utage: other.utage,
+            utage: other
+                .utage
+                .map(|utage| Utage::from_api(utage, other.buddy.as_deref())),
+            first_seen: None,
+            removed_at: None,
+        }
+    }
+}
+
+impl SongTrait for Song {
+    const DIFFICULTIES: &'static [&'static str] = &[
+        "basic",
+        "advanced",
+        "expert",
+        "master",
+        "remaster",
+        "dx_basic",
+        "dx_advanced",
+        "dx_expert",
+        "dx_master",
+        "dx_remaster",
+        "utage",
+    ];
+
+    fn key(&self) -> Cow<'_, str> {
+        Cow::Borrowed(&self.id)
+    }
+
+    fn title(&self) -> &str {
+        &self.title
+    }
+
+    fn artist(&self) -> &str {
+        &self.artist
+    }
+
+    fn image_id(&self) -> Option<&str> {
+        Some(&self.image_id)
+    }
+
+    fn title_reading(&self) -> Option<&str> {
+        Some(&self.title_reading)
+    }
+
+    fn categories(&self) -> Vec<&str> {
+        vec![&self.category]
+    }
+
+    fn category_names(&self) -> Vec<&str> {
+        vec![&self.category]
+    }
+
+    fn release_date(&self) -> Option<NaiveDate> {
+        self.release
+    }
+
+    fn versions(&self) -> Vec<&str> {
+        static VERSIONS: LazyLock<Vec<Version>> = LazyLock::new(get_all_versions);
+
+        let name = VERSIONS
+            .iter()
+            .find(|version| version.contains(&self.version))
+            .map(|version| version.name.as_ref());
+
+        std::iter::once(self.version.as_str()).chain(name).collect()
+    }
+
+    fn charts(&self) -> Vec<(&'static str, Level)> {
+        let dx_charts = self.dx_levels.iter().flat_map(|dx_levels| {
+            Self::DIFFICULTIES[5..10]
+                .iter()
+                .copied()
+                .zip([
+                    dx_levels.basic,
+                    dx_levels.advanced,
+                    dx_levels.expert,
+                    dx_levels.master,
+                    dx_levels.remaster,
+                ])
+                .filter_map(|(difficulty, level)| level.map(|level| (difficulty, level)))
+        });
+
+        self.levels
+            .iter()
+            .flat_map(LevelMap::charts)
+            .chain(dx_charts)
+            .chain(self.utage.iter().map(|utage| ("utage", utage.level)))
+            .collect()
+    }
+
+    fn has_flag(&self, flag: SongFlag) -> bool {
+        match flag {
+            SongFlag::New => self.is_new,
+            SongFlag::Locked => self.is_locked,
+            _ => false,
         }
     }
+
+    fn fields(&self) -> SongFields {
+        SongFields::default()
+            .field("title", &self.title)
+            .field("title_reading", &self.title_reading)
+            .field("artist", &self.artist)
+            .field("image_id", &self.image_id)
+            .field("category", &self.category)
+            .field("version", &self.version)
+            .optional_field("release", self.release)
+            .field("is_new", self.is_new)
+            .field("is_locked", self.is_locked)
+            .levels("levels", self.levels.iter().flat_map(LevelMap::charts))
+            .levels(
+                "dx_levels",
+                self.dx_levels.iter().flat_map(DXLevelMap::charts),
+            )
+            .optional_field("utage.level", self.utage.as_ref().map(|utage| utage.level))
+            .optional_field("utage.kanji", self.utage.as_ref().map(|utage| &utage.kanji))
+            .optional_field(
+                "utage.comment",
+                self.utage.as_ref().map(|utage| &utage.comment),
+            )
+            .optional_field(
+                "utage.buddy.left",
+                self.utage
+                    .as_ref()
+                    .and_then(|utage| utage.buddy)
+                    .map(|buddy| buddy.left),
+            )
+            .optional_field(
+                "utage.buddy.right",
+                self.utage
+                    .as_ref()
+                    .and_then(|utage| utage.buddy)
+                    .map(|buddy| buddy.right),
+            )
+    }
+
+    fn first_seen(&self) -> Option<NaiveDate> {
+        self.first_seen
+    }
+
+    fn removed_at(&self) -> Option<NaiveDate> {
+        self.removed_at
+    }
+
+    fn set_first_seen(&mut self, date: Option<NaiveDate>) {
+        self.first_seen = date;
+    }
+
+    fn set_removed_at(&mut self, date: Option<NaiveDate>) {
+        self.removed_at = date;
+    }
 }
 
-#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct Category {
     pub slug: Cow<'static, str>,
     pub name: Cow<'static, str>,
 }
 
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Version {
+    /// Code of the version's first update, songs share its first three digits
+    pub code: Cow<'static, str>,
+    pub name: Cow<'static, str>,
+    /// `None` when the date hasn't been recorded yet
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub jp_release: Option<NaiveDate>,
+    /// Whether the version was released on international cabinets
+    pub intl: bool,
+}
+
+impl Version {
+    /// Whether a song version code like `"24005"` belongs to this version
+    pub fn contains(&self, code: &str) -> bool {
+        code.len() == self.code.len() && code.get(..3) == self.code.get(..3)
+    }
+}
+
 #[derive(Serialize, Deserialize, Debug)]
 pub struct DataStore {
     name: Cow<'static, str>,
@@ -187,24 +463,171 @@ pub struct DataStore {
     last_updated: DateTime<Utc>,
     pub songs: Vec<Song>,
     pub categories: Vec<Category>,
+    #[serde(default)]
+    pub versions: Vec<Version>,
 }
 
 impl DataStore {
     pub fn new(name: &'static str, songs: Vec<Song>, categories: Vec<Category>) -> Self {
         Self {
             name: Cow::Borrowed(name),
-            count: songs.len(),
+            count: songs.iter().filter(|song| !song.is_removed()).count(),
             songs,
             last_updated: Utc::now(),
             categories,
+            versions: get_all_versions(),
         }
     }
+
+    /// Version the song was introduced in, `None` if its code is unknown
+    pub fn version_of(&self, song: &Song) -> Option<&Version> {
+        self.versions
+            .iter()
+            .find(|version| version.contains(&song.version))
+    }
 }
 
 impl DataStoreTrait for DataStore {
-    fn data_differs(&self, other: &Self) -> bool {
-        self.count != other.count
-            || !self.songs.iter().eq(other.songs.iter())
-            || !self.categories.iter().eq(other.categories.iter())
+    type Song = Song;
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn songs(&self) -> &[Song] {
+        &self.songs
+    }
+
+    fn last_updated(&self) -> DateTime<Utc> {
+        self.last_updated
+    }
+
+    fn definitions_differ(&self, other: &Self) -> bool {
+        !self.categories.iter().eq(other.categories.iter())
+            || !self.versions.iter().eq(other.versions.iter())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// "[協]Love You" as listed by the JP API, the overall level is the only one given
+    const BUDDY_ENTRY: &str = r#"{
+        "sort": "1644",
+        "title": "[協]Love You",
+        "title_kana": "LOVEYOU",
+        "artist": "Q;indivi [cover]",
+        "image_url": "daf6adeacb8fc300.png",
+        "catcode": "宴会場",
+        "release": "230914",
+        "version": "24000",
+        "lev_utage": "12?",
+        "kanji": "協",
+        "comment": "リア充専用譜面だよ！リア充向けだってば！",
+        "buddy": "○"
+    }"#;
+
+    fn parse(json: &str) -> Song {
+        serde_json::from_str::<SongFromAPI>(json).unwrap().into()
+    }
+
+    fn level(level: &str) -> Level {
+        level.parse().unwrap()
+    }
+
+    #[test]
+    fn buddy_halves_share_the_overall_level() {
+        let song = parse(BUDDY_ENTRY);
+        let utage = song.utage().unwrap();
+
+        assert_eq!(utage.level, level("12?"));
+        assert_eq!(utage.kanji, "協");
+        assert_eq!(
+            utage.buddy,
+            Some(BuddyLevels {
+                left: level("12?"),
+                right: level("12?"),
+            })
+        );
+        assert_eq!(song.charts(), vec![("utage", level("12?"))]);
+    }
+
+    #[test]
+    fn buddy_halves_use_per_player_levels() {
+        let json = BUDDY_ENTRY.replace(
+            r#""buddy": "○""#,
+            r#""buddy": "○", "lev_utage_left": "12", "lev_utage_right": "13+""#,
+        );
+        let song = parse(&json);
+
+        assert_eq!(
+            song.utage().unwrap().buddy,
+            Some(BuddyLevels {
+                left: level("12"),
+                right: level("13+"),
+            })
+        );
+
+        let fields = song.fields();
+        assert_eq!(
+            fields.get("utage.buddy.left").map(String::as_str),
+            Some("12")
+        );
+        assert_eq!(
+            fields.get("utage.buddy.right").map(String::as_str),
+            Some("13+")
+        );
+    }
+
+    #[test]
+    fn solo_utage_is_not_buddy() {
+        let json = BUDDY_ENTRY.replace(r#""buddy": "○""#, r#""buddy": """#);
+        let song = parse(&json);
+
+        assert!(!song.utage().unwrap().is_buddy());
+        assert_eq!(song.fields().get("utage.buddy.left"), None);
+    }
+
+    #[test]
+    fn version_contains_codes_of_its_updates() {
+        let versions = get_all_versions();
+        let version = |code: &str| {
+            versions
+                .iter()
+                .find(|version| version.code == code)
+                .unwrap()
+        };
+
+        assert!(version("24000").contains("24000"));
+        assert!(version("24000").contains("24005"));
+        assert!(!version("24000").contains("24500"));
+        assert!(version("18500").contains("18502"));
+        assert!(!version("18500").contains("18000"));
+        assert!(!version("24000").contains("2400"));
+        assert!(!version("24000").contains(""));
+    }
+
+    #[test]
+    fn every_version_has_a_release_date() {
+        let versions = get_all_versions();
+
+        assert!(versions.iter().all(|version| version.jp_release.is_some()));
+        assert!(versions.is_sorted_by_key(|version| version.jp_release));
+    }
+
+    #[test]
+    fn looks_up_the_version_of_a_song() {
+        let song = parse(BUDDY_ENTRY);
+        let data_store = DataStore::new("maimai_jp", vec![song.clone()], vec![]);
+
+        let version = data_store.version_of(&song).unwrap();
+        assert_eq!(version.name, "maimaiでらっくす BUDDiES");
+        assert_eq!(version.jp_release, NaiveDate::from_ymd_opt(2023, 9, 14));
+        assert_eq!(song.versions(), ["24000", "maimaiでらっくす BUDDiES"]);
+
+        let unknown = parse(&BUDDY_ENTRY.replace(r#""24000""#, r#""99000""#));
+        assert_eq!(data_store.version_of(&unknown), None);
+        assert_eq!(unknown.versions(), ["99000"]);
     }
 }
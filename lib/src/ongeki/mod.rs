@@ -15,6 +15,18 @@ impl Otoge for Ongeki {
     fn name() -> &'static str {
         "ongeki"
     }
+
+    fn display_name() -> &'static str {
+        "O.N.G.E.K.I."
+    }
+
+    fn homepage() -> &'static str {
+        "https://ongeki.sega.jp/"
+    }
+
+    fn jacket_base_url() -> Option<&'static str> {
+        Some("https://ongeki.sega.jp/assets/img/music/")
+    }
 }
 
 pub(crate) fn get_all_categories() -> Vec<Category> {
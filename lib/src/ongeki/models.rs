@@ -8,9 +8,11 @@ use super::get_all_categories;
 use crate::shared::deserializers::{
     all_default_values_as_none, bool_from_string, empty_string_as_none,
 };
-use crate::shared::traits::DataStore as DataStoreTrait;
+use crate::shared::diff::SongFields;
+use crate::shared::level::Level;
+use crate::shared::traits::{DataStore as DataStoreTrait, Song as SongTrait, SongFlag};
 
-#[derive(Serialize, Deserialize, Debug, Default, PartialEq)]
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
 #[allow(dead_code)]
 pub struct LevelMap {
     #[serde(deserialize_with = "empty_string_as_none")]
@@ -18,34 +20,54 @@ pub struct LevelMap {
     // FIXME: Be more explicit when deserializing API
     #[serde(alias = "lev_bas")]
     #[serde(default = "Option::default")]
-    basic: Option<String>,
+    basic: Option<Level>,
     #[serde(deserialize_with = "empty_string_as_none")]
     #[serde(skip_serializing_if = "Option::is_none")]
     // FIXME: Be more explicit when deserializing API
     #[serde(alias = "lev_adv")]
     #[serde(default = "Option::default")]
-    advanced: Option<String>,
+    advanced: Option<Level>,
     #[serde(deserialize_with = "empty_string_as_none")]
     #[serde(skip_serializing_if = "Option::is_none")]
     // FIXME: Be more explicit when deserializing API
     #[serde(alias = "lev_exc")]
     #[serde(default = "Option::default")]
-    expert: Option<String>,
+    expert: Option<Level>,
     #[serde(deserialize_with = "empty_string_as_none")]
     #[serde(skip_serializing_if = "Option::is_none")]
     // FIXME: Be more explicit when deserializing API
     #[serde(alias = "lev_mas")]
     #[serde(default = "Option::default")]
-    master: Option<String>,
+    master: Option<Level>,
     #[serde(deserialize_with = "empty_string_as_none")]
     #[serde(skip_serializing_if = "Option::is_none")]
     // FIXME: Be more explicit when deserializing API
     #[serde(alias = "lev_lnt")]
     #[serde(default = "Option::default")]
-    lunatic: Option<String>,
+    lunatic: Option<Level>,
 }
 
-#[derive(Serialize, Deserialize, Debug, Default, PartialEq)]
+impl LevelMap {
+    /// Difficulty names in the order the game lists them
+    pub const DIFFICULTIES: [&'static str; 5] =
+        ["basic", "advanced", "expert", "master", "lunatic"];
+
+    pub fn charts(&self) -> Vec<(&'static str, Level)> {
+        Self::DIFFICULTIES
+            .into_iter()
+            .zip([
+                self.basic,
+                self.advanced,
+                self.expert,
+                self.master,
+                self.lunatic,
+            ])
+            .filter_map(|(difficulty, level)| level.map(|level| (difficulty, level)))
+            .collect()
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
 pub struct Character {
     #[serde(deserialize_with = "empty_string_as_none")]
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -59,7 +81,7 @@ pub struct Character {
     name: Option<String>,
 }
 
-#[derive(Serialize, Deserialize, Debug, Default, PartialEq)]
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
 pub struct CategoryInSong {
     // FIXME: Be more explicit when deserializing API
     #[serde(alias = "category_id")]
@@ -69,7 +91,7 @@ pub struct CategoryInSong {
     pub name: Cow<'static, str>,
 }
 
-#[derive(Serialize, Deserialize, Debug, Default, PartialEq)]
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
 pub struct Chapter {
     #[serde(deserialize_with = "empty_string_as_none")]
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -125,7 +147,7 @@ pub struct SongFromAPI {
     character: Option<Character>,
 }
 
-#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct Song {
     id: String,
     title: String,
@@ -138,6 +160,10 @@ pub struct Song {
     is_bonus_track: bool,
     #[serde(skip_serializing_if = "Option::is_none")]
     copyright: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    first_seen: Option<NaiveDate>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    removed_at: Option<NaiveDate>,
 
     #[serde(skip_serializing_if = "Option::is_none")]
     chapter: Option<Chapter>,
@@ -166,11 +192,101 @@ impl From<SongFromAPI> for Song {
             category: other.category,
             levels: other.levels,
             character: other.character,
+            first_seen: None,
+            removed_at: None,
         }
     }
 }
 
-#[derive(Serialize, Deserialize, Debug, PartialEq)]
+impl SongTrait for Song {
+    const DIFFICULTIES: &'static [&'static str] = &LevelMap::DIFFICULTIES;
+
+    fn key(&self) -> Cow<'_, str> {
+        Cow::Borrowed(&self.id)
+    }
+
+    fn title(&self) -> &str {
+        &self.title
+    }
+
+    fn artist(&self) -> &str {
+        &self.artist
+    }
+
+    fn image_id(&self) -> Option<&str> {
+        Some(&self.image_id)
+    }
+
+    fn title_reading(&self) -> Option<&str> {
+        Some(&self.title_reading)
+    }
+
+    fn categories(&self) -> Vec<&str> {
+        vec![&self.category.id, &self.category.name]
+    }
+
+    fn category_names(&self) -> Vec<&str> {
+        vec![&self.category.name]
+    }
+
+    fn release_date(&self) -> Option<NaiveDate> {
+        Some(self.date)
+    }
+
+    fn charts(&self) -> Vec<(&'static str, Level)> {
+        self.levels.iter().flat_map(LevelMap::charts).collect()
+    }
+
+    fn has_flag(&self, flag: SongFlag) -> bool {
+        match flag {
+            SongFlag::New => self.is_new,
+            SongFlag::BonusTrack => self.is_bonus_track,
+            SongFlag::Lunatic => self.is_lunatic,
+            SongFlag::Locked => false,
+        }
+    }
+
+    fn fields(&self) -> SongFields {
+        let chapter = self.chapter.as_ref();
+        let character = self.character.as_ref();
+
+        SongFields::default()
+            .field("title", &self.title)
+            .field("title_reading", &self.title_reading)
+            .field("artist", &self.artist)
+            .field("date", self.date)
+            .field("image_id", &self.image_id)
+            .field("is_new", self.is_new)
+            .field("is_lunatic", self.is_lunatic)
+            .field("is_bonus_track", self.is_bonus_track)
+            .optional_field("copyright", self.copyright.as_ref())
+            .optional_field("chapter.id", chapter.and_then(|c| c.id.as_ref()))
+            .optional_field("chapter.name", chapter.and_then(|c| c.name.as_ref()))
+            .field("category.id", &self.category.id)
+            .field("category.name", &self.category.name)
+            .levels("levels", self.levels.iter().flat_map(LevelMap::charts))
+            .optional_field("character.id", character.and_then(|c| c.id.as_ref()))
+            .optional_field("character.name", character.and_then(|c| c.name.as_ref()))
+    }
+
+    fn first_seen(&self) -> Option<NaiveDate> {
+        self.first_seen
+    }
+
+    fn removed_at(&self) -> Option<NaiveDate> {
+        self.removed_at
+    }
+
+    fn set_first_seen(&mut self, date: Option<NaiveDate>) {
+        self.first_seen = date;
+    }
+
+    fn set_removed_at(&mut self, date: Option<NaiveDate>) {
+        self.removed_at = date;
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct Category {
     pub id: Cow<'static, str>,
     pub slug: Cow<'static, str>,
@@ -190,7 +306,7 @@ impl DataStore {
     pub fn new(name: &'static str, songs: Vec<Song>) -> Self {
         Self {
             name: Cow::Borrowed(name),
-            count: songs.len(),
+            count: songs.iter().filter(|song| !song.is_removed()).count(),
             songs,
             last_updated: Utc::now(),
             categories: get_all_categories(),
@@ -199,9 +315,21 @@ impl DataStore {
 }
 
 impl DataStoreTrait for DataStore {
-    fn data_differs(&self, other: &Self) -> bool {
-        self.count != other.count
-            || !self.songs.iter().eq(other.songs.iter())
-            || !self.categories.iter().eq(other.categories.iter())
+    type Song = Song;
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn songs(&self) -> &[Song] {
+        &self.songs
+    }
+
+    fn last_updated(&self) -> DateTime<Utc> {
+        self.last_updated
+    }
+
+    fn definitions_differ(&self, other: &Self) -> bool {
+        !self.categories.iter().eq(other.categories.iter())
     }
 }
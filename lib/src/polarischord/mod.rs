@@ -13,6 +13,14 @@ impl Otoge for PolarisChord {
     fn name() -> &'static str {
         "polarischord"
     }
+
+    fn display_name() -> &'static str {
+        "Polaris Chord"
+    }
+
+    fn homepage() -> &'static str {
+        "https://p.eagate.573.jp/game/polarischord/pc/"
+    }
 }
 
 pub(crate) fn get_all_categories() -> Vec<Category> {
@@ -1,11 +1,13 @@
 use std::borrow::Cow;
 
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, NaiveDate, Utc};
 use serde::{Deserialize, Serialize};
 
 use super::get_all_categories;
 use crate::shared::deserializers::{all_default_values_as_none, empty_string_as_none};
-use crate::shared::traits::DataStore as DataStoreTrait;
+use crate::shared::diff::SongFields;
+use crate::shared::level::Level;
+use crate::shared::traits::{DataStore as DataStoreTrait, Song as SongTrait};
 
 #[derive(Serialize)]
 pub struct APIInput {
@@ -20,7 +22,7 @@ impl Default for APIInput {
     }
 }
 
-#[derive(Serialize, Deserialize, Debug, Default, PartialEq)]
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
 #[allow(dead_code)]
 pub struct LevelMap {
     #[serde(deserialize_with = "all_default_values_as_none")]
@@ -45,6 +47,27 @@ pub struct LevelMap {
     polar: Option<i32>,
 }
 
+impl LevelMap {
+    /// Difficulty names in the order the game lists them
+    pub const DIFFICULTIES: [&'static str; 5] = ["easy", "normal", "hard", "influence", "polar"];
+
+    pub fn charts(&self) -> Vec<(&'static str, Level)> {
+        let level = |value: Option<i32>| value.and_then(|v| u32::try_from(v).ok()).map(Level::from);
+
+        Self::DIFFICULTIES
+            .into_iter()
+            .zip([
+                self.easy,
+                self.normal,
+                self.hard,
+                self.influence,
+                self.polar,
+            ])
+            .filter_map(|(difficulty, value)| level(value).map(|level| (difficulty, level)))
+            .collect()
+    }
+}
+
 #[derive(Deserialize, Debug)]
 pub struct SongFromAPI {
     music_id: String,
@@ -57,13 +80,17 @@ pub struct SongFromAPI {
     levels: LevelMap,
 }
 
-#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct Song {
     id: String,
     title: String,
     artist: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     license: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    first_seen: Option<NaiveDate>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    removed_at: Option<NaiveDate>,
     levels: LevelMap,
     categories: Vec<Category>,
 }
@@ -86,11 +113,69 @@ impl From<SongFromAPI> for Song {
             license: value.license,
             levels: value.levels,
             categories,
+            first_seen: None,
+            removed_at: None,
         }
     }
 }
 
-#[derive(Serialize, Deserialize, Debug, PartialEq)]
+impl SongTrait for Song {
+    const DIFFICULTIES: &'static [&'static str] = &LevelMap::DIFFICULTIES;
+
+    fn key(&self) -> Cow<'_, str> {
+        Cow::Borrowed(&self.id)
+    }
+
+    fn title(&self) -> &str {
+        &self.title
+    }
+
+    fn artist(&self) -> &str {
+        &self.artist
+    }
+
+    fn categories(&self) -> Vec<&str> {
+        self.categories
+            .iter()
+            .flat_map(|c| [c.slug.as_ref(), c.name.as_ref()])
+            .collect()
+    }
+
+    fn category_names(&self) -> Vec<&str> {
+        self.categories.iter().map(|c| c.name.as_ref()).collect()
+    }
+
+    fn charts(&self) -> Vec<(&'static str, Level)> {
+        self.levels.charts()
+    }
+
+    fn fields(&self) -> SongFields {
+        SongFields::default()
+            .field("title", &self.title)
+            .field("artist", &self.artist)
+            .optional_field("license", self.license.as_ref())
+            .levels("levels", self.levels.charts())
+            .list_field("categories", self.categories.iter().map(|c| &c.slug))
+    }
+
+    fn first_seen(&self) -> Option<NaiveDate> {
+        self.first_seen
+    }
+
+    fn removed_at(&self) -> Option<NaiveDate> {
+        self.removed_at
+    }
+
+    fn set_first_seen(&mut self, date: Option<NaiveDate>) {
+        self.first_seen = date;
+    }
+
+    fn set_removed_at(&mut self, date: Option<NaiveDate>) {
+        self.removed_at = date;
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct Category {
     pub bitflag: u32,
     pub slug: Cow<'static, str>,
@@ -110,7 +195,7 @@ impl DataStore {
     pub fn new(name: &'static str, songs: Vec<Song>) -> Self {
         Self {
             name: Cow::Borrowed(name),
-            count: songs.len(),
+            count: songs.iter().filter(|song| !song.is_removed()).count(),
             songs,
             last_updated: Utc::now(),
             categories: get_all_categories(),
@@ -119,9 +204,21 @@ impl DataStore {
 }
 
 impl DataStoreTrait for DataStore {
-    fn data_differs(&self, other: &Self) -> bool {
-        self.count != other.count
-            || !self.songs.iter().eq(other.songs.iter())
-            || !self.categories.iter().eq(other.categories.iter())
+    type Song = Song;
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn songs(&self) -> &[Song] {
+        &self.songs
+    }
+
+    fn last_updated(&self) -> DateTime<Utc> {
+        self.last_updated
+    }
+
+    fn definitions_differ(&self, other: &Self) -> bool {
+        !self.categories.iter().eq(other.categories.iter())
     }
 }
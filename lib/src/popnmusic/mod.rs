@@ -14,6 +14,18 @@ impl Otoge for PopNMusic {
     fn name() -> &'static str {
         "popnmusic"
     }
+
+    fn display_name() -> &'static str {
+        "pop'n music"
+    }
+
+    fn homepage() -> &'static str {
+        "https://p.eagate.573.jp/game/popn/popn29/"
+    }
+
+    fn jacket_base_url() -> Option<&'static str> {
+        Some("https://p.eagate.573.jp")
+    }
 }
 
 pub fn get_all_versions() -> Vec<Category> {
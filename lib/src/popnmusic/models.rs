@@ -1,10 +1,13 @@
 use std::borrow::Cow;
 
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, NaiveDate, Utc};
 use serde::{Deserialize, Serialize};
 
 use super::{get_all_bemani, get_all_categories, get_all_versions};
-use crate::shared::traits::DataStore as DataStoreTrait;
+use crate::shared::diff::SongFields;
+use crate::shared::ids::SyntheticId;
+use crate::shared::level::Level;
+use crate::shared::traits::{DataStore as DataStoreTrait, Song as SongTrait};
 
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq, Hash)]
 pub struct Category {
@@ -13,24 +16,64 @@ pub struct Category {
 }
 
 #[allow(dead_code)]
-#[derive(Serialize, Deserialize, Debug, Default, PartialEq, Eq, Hash)]
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq, Hash)]
 pub struct LevelMap {
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub light: Option<String>,
+    pub light: Option<Level>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub normal: Option<String>,
+    pub normal: Option<Level>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub hyper: Option<String>,
+    pub hyper: Option<Level>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub ex: Option<String>,
+    pub ex: Option<Level>,
 }
 
-#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Hash)]
+impl LevelMap {
+    /// Difficulty names in the order the game lists them
+    pub const DIFFICULTIES: [&'static str; 4] = ["light", "normal", "hyper", "ex"];
+
+    pub fn charts(&self) -> Vec<(&'static str, Level)> {
+        Self::DIFFICULTIES
+            .into_iter()
+            .zip([self.light, self.normal, self.hyper, self.ex])
+            .filter_map(|(difficulty, level)| level.map(|level| (difficulty, level)))
+            .collect()
+    }
+}
+
+/// Builds a level map from `(difficulty, level)` pairs as returned by [`LevelMap::charts`],
+/// unknown difficulties are ignored
+impl<'a> FromIterator<(&'a str, Level)> for LevelMap {
+    fn from_iter<I>(iter: I) -> Self
+    where
+        I: IntoIterator<Item = (&'a str, Level)>,
+    {
+        let mut levels = Self::default();
+        for (difficulty, level) in iter {
+            match difficulty {
+                "light" => levels.light = Some(level),
+                "normal" => levels.normal = Some(level),
+                "hyper" => levels.hyper = Some(level),
+                "ex" => levels.ex = Some(level),
+                _ => {}
+            }
+        }
+        levels
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
 pub struct Song {
+    /// otoge-assigned id, the site doesn't expose one
+    pub id: String,
     pub image_id: String,
     pub genre: String,
     pub title: String,
     pub artist: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub first_seen: Option<NaiveDate>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub removed_at: Option<NaiveDate>,
     pub levels: LevelMap,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub version: Option<Category>,
@@ -40,6 +83,97 @@ pub struct Song {
     pub recommendation_categories: Vec<Category>,
 }
 
+impl SongTrait for Song {
+    const DIFFICULTIES: &'static [&'static str] = &LevelMap::DIFFICULTIES;
+
+    fn key(&self) -> Cow<'_, str> {
+        Cow::Borrowed(&self.id)
+    }
+
+    fn title(&self) -> &str {
+        &self.title
+    }
+
+    fn artist(&self) -> &str {
+        &self.artist
+    }
+
+    fn image_id(&self) -> Option<&str> {
+        Some(&self.image_id)
+    }
+
+    fn categories(&self) -> Vec<&str> {
+        self.recommendation_categories
+            .iter()
+            .flat_map(|c| [c.id.as_ref(), c.name.as_ref()])
+            .collect()
+    }
+
+    fn category_names(&self) -> Vec<&str> {
+        self.recommendation_categories
+            .iter()
+            .map(|c| c.name.as_ref())
+            .collect()
+    }
+
+    fn versions(&self) -> Vec<&str> {
+        self.version
+            .iter()
+            .flat_map(|v| [v.id.as_ref(), v.name.as_ref()])
+            .collect()
+    }
+
+    fn charts(&self) -> Vec<(&'static str, Level)> {
+        self.levels.charts()
+    }
+
+    /// Version, bemani and categories compare by name only, their ids come from the same
+    /// definitions as the names so an id change shows up as `definitions_changed` instead
+    fn fields(&self) -> SongFields {
+        SongFields::default()
+            .field("genre", &self.genre)
+            .field("title", &self.title)
+            .field("artist", &self.artist)
+            .field("image_id", &self.image_id)
+            .levels("levels", self.levels.charts())
+            .optional_field("version", self.version.as_ref().map(|v| &v.name))
+            .list_field("bemani", self.bemani.iter().map(|b| &b.name))
+            .list_field(
+                "recommendation_categories",
+                self.recommendation_categories.iter().map(|c| &c.name),
+            )
+    }
+
+    fn first_seen(&self) -> Option<NaiveDate> {
+        self.first_seen
+    }
+
+    fn removed_at(&self) -> Option<NaiveDate> {
+        self.removed_at
+    }
+
+    fn set_first_seen(&mut self, date: Option<NaiveDate>) {
+        self.first_seen = date;
+    }
+
+    fn set_removed_at(&mut self, date: Option<NaiveDate>) {
+        self.removed_at = date;
+    }
+}
+
+impl SyntheticId for Song {
+    fn fingerprints(&self) -> Vec<String> {
+        vec![
+            format!("{}\t{}\t{}", self.genre, self.title, self.artist),
+            self.image_id.clone(),
+        ]
+    }
+
+    fn set_id(&mut self, id: String) {
+        self.id = id;
+    }
+}
+
 #[derive(Serialize, Deserialize, Debug)]
 pub struct DataStore {
     name: Cow<'static, str>,
@@ -55,7 +189,7 @@ impl DataStore {
     pub fn new(name: &'static str, songs: Vec<Song>) -> Self {
         Self {
             name: Cow::Borrowed(name),
-            count: songs.len(),
+            count: songs.iter().filter(|song| !song.is_removed()).count(),
             songs,
             last_updated: Utc::now(),
             versions: get_all_versions(),
@@ -66,10 +200,22 @@ impl DataStore {
 }
 
 impl DataStoreTrait for DataStore {
-    fn data_differs(&self, other: &Self) -> bool {
-        self.count != other.count
-            || !self.songs.iter().eq(other.songs.iter())
-            || !self.versions.iter().eq(other.versions.iter())
+    type Song = Song;
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn songs(&self) -> &[Song] {
+        &self.songs
+    }
+
+    fn last_updated(&self) -> DateTime<Utc> {
+        self.last_updated
+    }
+
+    fn definitions_differ(&self, other: &Self) -> bool {
+        !self.versions.iter().eq(other.versions.iter())
             || !self.bemani.iter().eq(other.bemani.iter())
             || !self.categories.iter().eq(other.categories.iter())
     }
@@ -0,0 +1,123 @@
+use std::collections::{HashMap, HashSet};
+
+use chrono::NaiveDate;
+
+use super::traits::Song as SongTrait;
+
+/// Carries `first_seen`/`removed_at` over from the previous song list into a freshly fetched one.
+///
+/// Songs new to `current` are marked as first seen on `today`. Songs missing from `current` are
+/// appended as tombstones removed on `today`, existing tombstones are kept as-is, and a tombstoned
+/// song that reappears upstream is revived with its original `first_seen`.
+pub fn track_availability<S>(previous: &[S], current: &mut Vec<S>, today: NaiveDate)
+where
+    S: SongTrait + Clone,
+{
+    let previous_by_key: HashMap<_, _> = previous.iter().map(|song| (song.key(), song)).collect();
+
+    for song in current.iter_mut() {
+        match previous_by_key.get(&song.key()) {
+            Some(previous_song) => song.set_first_seen(previous_song.first_seen()),
+            None => song.set_first_seen(Some(today)),
+        }
+        song.set_removed_at(None);
+    }
+
+    let current_keys: HashSet<String> = current.iter().map(|s| s.key().into_owned()).collect();
+
+    let tombstones: Vec<S> = previous
+        .iter()
+        .filter(|song| !current_keys.contains(song.key().as_ref()))
+        .map(|song| {
+            let mut tombstone = song.clone();
+            if tombstone.removed_at().is_none() {
+                tombstone.set_removed_at(Some(today));
+            }
+            tombstone
+        })
+        .collect();
+
+    current.extend(tombstones);
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::chunithm::models::Song;
+    use crate::shared::test_songs::{chunithm_song, songs};
+
+    use super::*;
+
+    fn date(day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2026, 8, day).unwrap()
+    }
+
+    fn dates(songs: &[Song]) -> Vec<(&str, Option<NaiveDate>, Option<NaiveDate>)> {
+        songs
+            .iter()
+            .map(|song| (song.title(), song.first_seen(), song.removed_at()))
+            .collect()
+    }
+
+    #[test]
+    fn marks_new_songs_as_first_seen_today() {
+        let mut current: Vec<Song> = songs(&chunithm_song("1", "A", "13"));
+        track_availability(&[], &mut current, date(1));
+
+        assert_eq!(dates(&current), [("A", Some(date(1)), None)]);
+    }
+
+    #[test]
+    fn carries_first_seen_over() {
+        let mut previous: Vec<Song> = songs(&chunithm_song("1", "A", "13"));
+        previous[0].set_first_seen(Some(date(1)));
+
+        let mut current: Vec<Song> = songs(&chunithm_song("1", "A (renamed)", "13+"));
+        track_availability(&previous, &mut current, date(2));
+
+        assert_eq!(dates(&current), [("A (renamed)", Some(date(1)), None)]);
+    }
+
+    #[test]
+    fn tombstones_missing_songs() {
+        let mut previous: Vec<Song> =
+            songs(&[chunithm_song("1", "A", "13"), chunithm_song("2", "B", "14")].concat());
+        previous[0].set_first_seen(Some(date(1)));
+        previous[1].set_first_seen(Some(date(1)));
+
+        let mut current: Vec<Song> = songs(&chunithm_song("1", "A", "13"));
+        track_availability(&previous, &mut current, date(2));
+
+        assert_eq!(
+            dates(&current),
+            [
+                ("A", Some(date(1)), None),
+                ("B", Some(date(1)), Some(date(2)))
+            ]
+        );
+
+        // An existing tombstone keeps its removal date
+        let previous = current.clone();
+        let mut current: Vec<Song> = songs(&chunithm_song("1", "A", "13"));
+        track_availability(&previous, &mut current, date(3));
+
+        assert_eq!(
+            dates(&current),
+            [
+                ("A", Some(date(1)), None),
+                ("B", Some(date(1)), Some(date(2)))
+            ]
+        );
+    }
+
+    #[test]
+    fn revives_songs_that_reappear() {
+        let mut previous: Vec<Song> = songs(&chunithm_song("2", "B", "14"));
+        previous[0].set_first_seen(Some(date(1)));
+        previous[0].set_removed_at(Some(date(2)));
+
+        let mut current: Vec<Song> = songs(&chunithm_song("2", "B", "14"));
+        track_availability(&previous, &mut current, date(3));
+
+        assert_eq!(dates(&current), [("B", Some(date(1)), None)]);
+    }
+}
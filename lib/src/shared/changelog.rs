@@ -0,0 +1,147 @@
+use std::path::Path;
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use tokio::io::AsyncWriteExt;
+
+use super::diff::{DataStoreDiff, SongModification, SongSummary};
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ChangelogEntry {
+    pub date: DateTime<Utc>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub added: Vec<SongSummary>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub removed: Vec<SongSummary>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub modified: Vec<SongModification>,
+    /// Category or version definitions changed, which rewrites the data store on its own
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub definitions_changed: bool,
+}
+
+impl ChangelogEntry {
+    pub fn new(date: DateTime<Utc>, diff: DataStoreDiff) -> Self {
+        Self {
+            date,
+            added: diff.added,
+            removed: diff.removed,
+            modified: diff.modified,
+            definitions_changed: diff.definitions_changed,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty()
+            && self.removed.is_empty()
+            && self.modified.is_empty()
+            && !self.definitions_changed
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct Changelog {
+    #[serde(default)]
+    pub entries: Vec<ChangelogEntry>,
+}
+
+impl Changelog {
+    pub async fn load(path: &Path) -> Result<Self> {
+        if !tokio::fs::try_exists(path).await? {
+            return Ok(Self::default());
+        }
+
+        let contents = tokio::fs::read_to_string(path).await?;
+        Ok(toml::from_str(contents.as_str())?)
+    }
+
+    /// Appends a single entry to the changelog file without rewriting the existing entries
+    pub async fn append(path: &Path, entry: ChangelogEntry) -> Result<()> {
+        let contents = toml::to_string(&Self {
+            entries: vec![entry],
+        })?;
+
+        let mut file = tokio::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .await?;
+
+        if file.metadata().await?.len() > 0 {
+            file.write_all(b"\n").await?;
+        }
+        file.write_all(contents.as_bytes()).await?;
+
+        Ok(())
+    }
+
+    pub fn since(&self, date: DateTime<Utc>) -> impl Iterator<Item = &ChangelogEntry> {
+        self.entries.iter().filter(move |entry| entry.date >= date)
+    }
+
+    pub fn added_since(&self, date: DateTime<Utc>) -> impl Iterator<Item = &SongSummary> {
+        self.since(date).flat_map(|entry| entry.added.iter())
+    }
+
+    pub fn removed_since(&self, date: DateTime<Utc>) -> impl Iterator<Item = &SongSummary> {
+        self.since(date).flat_map(|entry| entry.removed.iter())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+
+    fn summary(key: &str) -> SongSummary {
+        SongSummary {
+            key: key.to_owned(),
+            title: format!("Song {key}"),
+        }
+    }
+
+    fn entry(day: u32, added: &[&str], removed: &[&str]) -> ChangelogEntry {
+        ChangelogEntry::new(
+            Utc.with_ymd_and_hms(2026, 8, day, 3, 0, 0).unwrap(),
+            DataStoreDiff {
+                added: added.iter().map(|key| summary(key)).collect(),
+                removed: removed.iter().map(|key| summary(key)).collect(),
+                ..DataStoreDiff::default()
+            },
+        )
+    }
+
+    fn keys<'a>(songs: impl Iterator<Item = &'a SongSummary>) -> Vec<&'a str> {
+        songs.map(|song| song.key.as_str()).collect()
+    }
+
+    #[tokio::test]
+    async fn appends_and_reads_back_entries() -> Result<()> {
+        let path =
+            std::env::temp_dir().join(format!("otoge-changelog-{}.toml", std::process::id()));
+        let _ = tokio::fs::remove_file(&path).await;
+
+        assert_eq!(Changelog::load(&path).await?, Changelog::default());
+
+        Changelog::append(&path, entry(1, &["1", "2"], &[])).await?;
+        Changelog::append(&path, entry(2, &["3"], &["1"])).await?;
+        let changelog = Changelog::load(&path).await?;
+        tokio::fs::remove_file(&path).await?;
+
+        assert_eq!(
+            changelog.entries,
+            [entry(1, &["1", "2"], &[]), entry(2, &["3"], &["1"])]
+        );
+
+        let since = Utc.with_ymd_and_hms(2026, 8, 2, 0, 0, 0).unwrap();
+        assert_eq!(changelog.since(since).count(), 1);
+        assert_eq!(keys(changelog.added_since(since)), ["3"]);
+        assert_eq!(keys(changelog.removed_since(since)), ["1"]);
+
+        let since = Utc.with_ymd_and_hms(2026, 8, 1, 0, 0, 0).unwrap();
+        assert_eq!(keys(changelog.added_since(since)), ["1", "2", "3"]);
+        Ok(())
+    }
+}
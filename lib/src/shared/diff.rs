@@ -0,0 +1,296 @@
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::fmt::Display;
+
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+use super::level::Level;
+use super::traits::Song as SongTrait;
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct SongSummary {
+    pub key: String,
+    pub title: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct FieldChange {
+    pub field: Cow<'static, str>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub before: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub after: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct SongModification {
+    pub key: String,
+    pub title: String,
+    pub changes: Vec<FieldChange>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct DataStoreDiff {
+    pub added: Vec<SongSummary>,
+    pub removed: Vec<SongSummary>,
+    pub modified: Vec<SongModification>,
+    /// Whether the category/version definitions shipped with the data store changed
+    pub definitions_changed: bool,
+}
+
+impl DataStoreDiff {
+    pub fn between<S>(old_songs: &[S], new_songs: &[S], definitions_changed: bool) -> Self
+    where
+        S: SongTrait,
+    {
+        // Tombstones are compared as if they were absent, so a removal shows up as such
+        let old_songs: Vec<&S> = old_songs.iter().filter(|s| !s.is_removed()).collect();
+        let new_songs: Vec<&S> = new_songs.iter().filter(|s| !s.is_removed()).collect();
+
+        let old_by_key = by_key(&old_songs, "old");
+        let new_by_key = by_key(&new_songs, "new");
+
+        let mut diff = Self {
+            definitions_changed,
+            ..Self::default()
+        };
+
+        for new_song in new_songs.iter().copied() {
+            let key = new_song.key();
+            // Only the first song with a duplicate key is compared
+            if !std::ptr::eq(new_by_key[&key], new_song) {
+                continue;
+            }
+
+            let Some(old_song) = old_by_key.get(&key) else {
+                diff.added.push(SongSummary::of(new_song));
+                continue;
+            };
+
+            let changes = old_song.fields().changes_to(&new_song.fields());
+            if !changes.is_empty() {
+                diff.modified.push(SongModification {
+                    key: key.into_owned(),
+                    title: new_song.title().to_owned(),
+                    changes,
+                });
+            }
+        }
+
+        for old_song in old_songs.iter().copied() {
+            let key = old_song.key();
+            if std::ptr::eq(old_by_key[&key], old_song) && !new_by_key.contains_key(&key) {
+                diff.removed.push(SongSummary::of(old_song));
+            }
+        }
+
+        diff
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty()
+            && self.removed.is_empty()
+            && self.modified.is_empty()
+            && !self.definitions_changed
+    }
+}
+
+/// Songs by key, keeping the first song of a duplicate key. Keys are unique in every game so a
+/// duplicate means the fetched data is broken, it is logged rather than failing the diff
+fn by_key<'a, S>(songs: &[&'a S], side: &str) -> HashMap<Cow<'a, str>, &'a S>
+where
+    S: SongTrait,
+{
+    let mut by_key: HashMap<Cow<'a, str>, &'a S> = HashMap::with_capacity(songs.len());
+    for song in songs.iter().copied() {
+        if let Some(first) = by_key.get(&song.key()) {
+            warn!(
+                "Duplicate key {} in the {side} songs: {:?} and {:?}",
+                song.key(),
+                first.title(),
+                song.title(),
+            );
+            continue;
+        }
+        by_key.insert(song.key(), song);
+    }
+    by_key
+}
+
+impl SongSummary {
+    fn of<S>(song: &S) -> Self
+    where
+        S: SongTrait,
+    {
+        Self {
+            key: song.key().into_owned(),
+            title: song.title().to_owned(),
+        }
+    }
+}
+
+/// Flattened, comparable representation of the fields of a song
+#[derive(Debug, Default)]
+pub struct SongFields(Vec<(Cow<'static, str>, String)>);
+
+impl SongFields {
+    pub fn field(mut self, name: impl Into<Cow<'static, str>>, value: impl Display) -> Self {
+        self.0.push((name.into(), value.to_string()));
+        self
+    }
+
+    pub fn optional_field<T>(self, name: impl Into<Cow<'static, str>>, value: Option<T>) -> Self
+    where
+        T: Display,
+    {
+        match value {
+            Some(value) => self.field(name, value),
+            None => self,
+        }
+    }
+
+    pub fn list_field<I, T>(self, name: impl Into<Cow<'static, str>>, values: I) -> Self
+    where
+        I: IntoIterator<Item = T>,
+        T: AsRef<str>,
+    {
+        let joined = values
+            .into_iter()
+            .map(|value| value.as_ref().to_owned())
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        if joined.is_empty() {
+            self
+        } else {
+            self.field(name, joined)
+        }
+    }
+
+    pub fn levels<I>(self, prefix: &'static str, charts: I) -> Self
+    where
+        I: IntoIterator<Item = (&'static str, Level)>,
+    {
+        charts
+            .into_iter()
+            .fold(self, |fields, (difficulty, level)| {
+                fields.field(format!("{prefix}.{difficulty}"), level)
+            })
+    }
+
+    pub(crate) fn get(&self, name: &str) -> Option<&String> {
+        self.0
+            .iter()
+            .find(|(field, _)| field == name)
+            .map(|(_, value)| value)
+    }
+
+    fn changes_to(&self, other: &Self) -> Vec<FieldChange> {
+        let mut changes = vec![];
+
+        for (name, before) in &self.0 {
+            let after = other.get(name);
+            if after != Some(before) {
+                changes.push(FieldChange {
+                    field: name.clone(),
+                    before: Some(before.clone()),
+                    after: after.cloned(),
+                });
+            }
+        }
+
+        for (name, after) in &other.0 {
+            if self.get(name).is_none() {
+                changes.push(FieldChange {
+                    field: name.clone(),
+                    before: None,
+                    after: Some(after.clone()),
+                });
+            }
+        }
+
+        changes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::chunithm::models::Song;
+    use crate::shared::test_songs::{chunithm_song, songs};
+
+    use super::*;
+
+    fn diff(old: &[String], new: &[String]) -> DataStoreDiff {
+        let old: Vec<Song> = songs(&old.concat());
+        let new: Vec<Song> = songs(&new.concat());
+        DataStoreDiff::between(&old, &new, false)
+    }
+
+    fn keys(songs: &[SongSummary]) -> Vec<&str> {
+        songs.iter().map(|song| song.key.as_str()).collect()
+    }
+
+    #[test]
+    fn finds_added_songs() {
+        let diff = diff(
+            &[chunithm_song("1", "A", "13")],
+            &[chunithm_song("1", "A", "13"), chunithm_song("2", "B", "14")],
+        );
+
+        assert_eq!(keys(&diff.added), ["2"]);
+        assert!(diff.removed.is_empty());
+        assert!(diff.modified.is_empty());
+    }
+
+    #[test]
+    fn finds_removed_songs() {
+        let diff = diff(
+            &[chunithm_song("1", "A", "13"), chunithm_song("2", "B", "14")],
+            &[chunithm_song("2", "B", "14")],
+        );
+
+        assert_eq!(keys(&diff.removed), ["1"]);
+        assert!(diff.added.is_empty());
+        assert!(!diff.is_empty());
+    }
+
+    #[test]
+    fn finds_changed_fields() {
+        let diff = diff(
+            &[chunithm_song("1", "A", "13")],
+            &[chunithm_song("1", "A", "13+")],
+        );
+
+        assert_eq!(
+            diff.modified,
+            [SongModification {
+                key: "1".to_owned(),
+                title: "A".to_owned(),
+                changes: vec![FieldChange {
+                    field: Cow::Borrowed("levels.master"),
+                    before: Some("13".to_owned()),
+                    after: Some("13+".to_owned()),
+                }],
+            }]
+        );
+    }
+
+    #[test]
+    fn unchanged_songs_give_an_empty_diff() {
+        let songs = [chunithm_song("1", "A", "13"), chunithm_song("2", "B", "14")];
+        assert!(diff(&songs, &songs).is_empty());
+    }
+
+    #[test]
+    fn compares_the_first_song_of_a_duplicate_key() {
+        let duplicates = [
+            chunithm_song("1", "A", "13"),
+            chunithm_song("1", "A (copy)", "14"),
+        ];
+
+        assert!(diff(&[chunithm_song("1", "A", "13")], &duplicates).is_empty());
+        assert_eq!(keys(&diff(&duplicates, &[]).removed), ["1"]);
+        assert_eq!(keys(&diff(&[], &duplicates).added), ["1"]);
+    }
+}
@@ -0,0 +1,324 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+/// Songs from games whose upstream doesn't provide a stable id.
+pub trait SyntheticId {
+    /// Values identifying the song, ordered from most to least specific.
+    ///
+    /// A song keeps its id as long as any one of these still matches the registered song, so
+    /// a change to e.g. the jacket won't cause a new id to be assigned. Retired songs only get
+    /// their id back when the first, most specific fingerprint matches.
+    fn fingerprints(&self) -> Vec<String>;
+    fn set_id(&mut self, id: String);
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct IdEntry {
+    pub id: u32,
+    pub fingerprints: Vec<String>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub retired: bool,
+}
+
+/// Persisted mapping from song fingerprints to otoge-assigned ids.
+///
+/// Ids are handed out sequentially and never reused: songs no longer present upstream are kept
+/// as retired entries, and only get their id back if the very same song reappears.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct IdRegistry {
+    last_id: u32,
+    #[serde(default)]
+    songs: Vec<IdEntry>,
+}
+
+impl IdRegistry {
+    pub async fn load(path: &Path) -> Result<Self> {
+        if !tokio::fs::try_exists(path).await? {
+            return Ok(Self::default());
+        }
+
+        let contents = tokio::fs::read_to_string(path).await?;
+        Ok(toml::from_str(contents.as_str())?)
+    }
+
+    pub async fn save(&self, path: &Path) -> Result<()> {
+        let contents = toml::to_string(self)?;
+        tokio::fs::write(path, contents).await?;
+        Ok(())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.songs.is_empty()
+    }
+
+    pub fn entries(&self) -> &[IdEntry] {
+        &self.songs
+    }
+
+    pub fn assign<S>(&mut self, songs: &mut [S])
+    where
+        S: SyntheticId,
+    {
+        let fingerprints: Vec<Vec<String>> = songs.iter().map(|s| s.fingerprints()).collect();
+        let mut assigned: Vec<Option<usize>> = vec![None; songs.len()];
+        let mut claimed = vec![false; self.songs.len()];
+
+        // The very same song keeps its entry, even when another song shares some fingerprints
+        let mut exact: HashMap<&[String], usize> = HashMap::new();
+        for (entry_index, entry) in self.songs.iter().enumerate() {
+            exact
+                .entry(entry.fingerprints.as_slice())
+                .and_modify(|existing| {
+                    if self.songs[*existing].retired && !entry.retired {
+                        *existing = entry_index;
+                    }
+                })
+                .or_insert(entry_index);
+        }
+        for (song_index, song_fingerprints) in fingerprints.iter().enumerate() {
+            if let Some(&entry_index) = exact.get(song_fingerprints.as_slice())
+                && !claimed[entry_index]
+            {
+                claimed[entry_index] = true;
+                assigned[song_index] = Some(entry_index);
+            }
+        }
+
+        let depth = fingerprints.iter().map(Vec::len).max().unwrap_or(0);
+
+        // Match the most specific fingerprint of every song first, so a looser fingerprint
+        // can't steal an entry that belongs to another song.
+        for level in 0..depth {
+            let mut index: HashMap<&str, usize> = HashMap::new();
+            for (entry_index, entry) in self.songs.iter().enumerate() {
+                // Retired songs only come back on their most specific fingerprint, a new song
+                // reusing e.g. the jacket of a retired one must get a new id
+                if claimed[entry_index] || (level > 0 && entry.retired) {
+                    continue;
+                }
+
+                if let Some(fingerprint) = entry.fingerprints.get(level) {
+                    // Prefer active entries over retired ones with the same fingerprint
+                    index
+                        .entry(fingerprint.as_str())
+                        .and_modify(|existing| {
+                            if self.songs[*existing].retired && !entry.retired {
+                                *existing = entry_index;
+                            }
+                        })
+                        .or_insert(entry_index);
+                }
+            }
+
+            for (song_index, song_fingerprints) in fingerprints.iter().enumerate() {
+                if assigned[song_index].is_some() {
+                    continue;
+                }
+
+                let Some(fingerprint) = song_fingerprints.get(level) else {
+                    continue;
+                };
+
+                if let Some(&entry_index) = index.get(fingerprint.as_str())
+                    && !claimed[entry_index]
+                {
+                    claimed[entry_index] = true;
+                    assigned[song_index] = Some(entry_index);
+                }
+            }
+        }
+
+        for ((song, song_fingerprints), assigned) in
+            songs.iter_mut().zip(fingerprints).zip(assigned)
+        {
+            let entry_index = match assigned {
+                Some(entry_index) => entry_index,
+                None => {
+                    self.last_id += 1;
+                    self.songs.push(IdEntry {
+                        id: self.last_id,
+                        fingerprints: vec![],
+                        retired: false,
+                    });
+                    claimed.push(true);
+                    self.songs.len() - 1
+                }
+            };
+
+            let entry = &mut self.songs[entry_index];
+            entry.fingerprints = song_fingerprints;
+            entry.retired = false;
+            song.set_id(entry.id.to_string());
+        }
+
+        for (entry, claimed) in self.songs.iter_mut().zip(claimed) {
+            if !claimed {
+                entry.retired = true;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct TestSong {
+        id: Option<String>,
+        title: &'static str,
+        artist: &'static str,
+        jacket: &'static str,
+    }
+
+    impl TestSong {
+        fn new(title: &'static str, artist: &'static str, jacket: &'static str) -> Self {
+            Self {
+                id: None,
+                title,
+                artist,
+                jacket,
+            }
+        }
+    }
+
+    impl SyntheticId for TestSong {
+        fn fingerprints(&self) -> Vec<String> {
+            vec![
+                format!("{}\t{}", self.title, self.artist),
+                self.jacket.to_owned(),
+            ]
+        }
+
+        fn set_id(&mut self, id: String) {
+            self.id = Some(id);
+        }
+    }
+
+    fn assign(registry: &mut IdRegistry, mut songs: Vec<TestSong>) -> Vec<String> {
+        registry.assign(&mut songs);
+        songs.into_iter().map(|song| song.id.unwrap()).collect()
+    }
+
+    #[test]
+    fn assigns_sequential_ids() {
+        let mut registry = IdRegistry::default();
+        let ids = assign(
+            &mut registry,
+            vec![
+                TestSong::new("A", "x", "a.png"),
+                TestSong::new("B", "y", "b.png"),
+            ],
+        );
+
+        assert_eq!(ids, ["1", "2"]);
+    }
+
+    #[test]
+    fn keeps_id_when_jacket_changes() {
+        let mut registry = IdRegistry::default();
+        assign(
+            &mut registry,
+            vec![
+                TestSong::new("A", "x", "a.png"),
+                TestSong::new("B", "y", "b.png"),
+            ],
+        );
+
+        let ids = assign(
+            &mut registry,
+            vec![
+                TestSong::new("A", "x", "new.png"),
+                TestSong::new("B", "y", "b.png"),
+            ],
+        );
+
+        assert_eq!(ids, ["1", "2"]);
+        assert_eq!(registry.entries()[0].fingerprints[1], "new.png");
+    }
+
+    #[test]
+    fn keeps_id_when_title_changes() {
+        let mut registry = IdRegistry::default();
+        assign(
+            &mut registry,
+            vec![
+                TestSong::new("A", "x", "a.png"),
+                TestSong::new("B", "y", "b.png"),
+            ],
+        );
+
+        let ids = assign(
+            &mut registry,
+            vec![
+                TestSong::new("B", "y", "b.png"),
+                TestSong::new("A (fixed)", "x", "a.png"),
+            ],
+        );
+
+        assert_eq!(ids, ["2", "1"]);
+    }
+
+    #[test]
+    fn revives_retired_song() {
+        let mut registry = IdRegistry::default();
+        assign(
+            &mut registry,
+            vec![
+                TestSong::new("A", "x", "a.png"),
+                TestSong::new("B", "y", "b.png"),
+            ],
+        );
+
+        assign(&mut registry, vec![TestSong::new("B", "y", "b.png")]);
+        assert!(registry.entries()[0].retired);
+
+        let ids = assign(
+            &mut registry,
+            vec![
+                TestSong::new("A", "x", "a.png"),
+                TestSong::new("B", "y", "b.png"),
+            ],
+        );
+
+        assert_eq!(ids, ["1", "2"]);
+        assert!(!registry.entries()[0].retired);
+    }
+
+    #[test]
+    fn never_reuses_retired_id_for_a_different_song() {
+        let mut registry = IdRegistry::default();
+        assign(&mut registry, vec![TestSong::new("A", "x", "shared.png")]);
+        assign(&mut registry, vec![]);
+
+        let ids = assign(&mut registry, vec![TestSong::new("C", "z", "shared.png")]);
+
+        assert_eq!(ids, ["2"]);
+        assert!(registry.entries()[0].retired);
+    }
+
+    #[test]
+    fn keeps_ids_of_songs_with_same_title_and_artist() {
+        let mut registry = IdRegistry::default();
+        assign(
+            &mut registry,
+            vec![
+                TestSong::new("A", "x", "1.png"),
+                TestSong::new("A", "x", "2.png"),
+            ],
+        );
+
+        // Upstream order isn't stable, the jacket tells the two songs apart
+        let ids = assign(
+            &mut registry,
+            vec![
+                TestSong::new("A", "x", "2.png"),
+                TestSong::new("A", "x", "1.png"),
+            ],
+        );
+
+        assert_eq!(ids, ["2", "1"]);
+    }
+}
@@ -0,0 +1,38 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+/// Index of the local jacket mirror, mapping every song key to its file in the same directory
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct JacketManifest {
+    #[serde(default)]
+    pub jackets: BTreeMap<String, JacketEntry>,
+    /// Songs whose jacket couldn't be downloaded in the last run, with the url that was tried
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub missing: BTreeMap<String, String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct JacketEntry {
+    pub image_id: String,
+    /// File name derived from the image contents, shared by songs with the same jacket
+    pub file: String,
+}
+
+impl JacketManifest {
+    pub async fn load(path: &Path) -> Result<Self> {
+        if !tokio::fs::try_exists(path).await? {
+            return Ok(Self::default());
+        }
+
+        let contents = tokio::fs::read_to_string(path).await?;
+        Ok(toml::from_str(contents.as_str())?)
+    }
+
+    pub async fn save(&self, path: &Path) -> Result<()> {
+        tokio::fs::write(path, toml::to_string(self)?).await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,231 @@
+use std::cmp::Ordering;
+use std::fmt;
+use std::str::FromStr;
+
+use serde::de::{Deserialize, Deserializer, Error};
+use serde::ser::{Serialize, Serializer};
+
+/// A chart difficulty level as displayed by the games, e.g. `13`, `13+` or `13+?`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Level {
+    /// A confirmed level, e.g. `13` or `13+`.
+    Known { base: u32, plus: bool },
+    /// A level marked as provisional with a trailing `?`, e.g. `13+?` (maimai utage).
+    Tentative { base: u32, plus: bool },
+    /// A level that is not disclosed at all, shown as `?`.
+    Unknown,
+}
+
+impl Level {
+    pub fn base(&self) -> Option<u32> {
+        match self {
+            Self::Known { base, .. } | Self::Tentative { base, .. } => Some(*base),
+            Self::Unknown => None,
+        }
+    }
+
+    pub fn is_plus(&self) -> bool {
+        match self {
+            Self::Known { plus, .. } | Self::Tentative { plus, .. } => *plus,
+            Self::Unknown => false,
+        }
+    }
+
+    /// Level as a number for range queries, `+` counts as half a level so `13+` is `13.5`
+    pub fn numeric(&self) -> Option<f64> {
+        self.base()
+            .map(|base| f64::from(base) + if self.is_plus() { 0.5 } else { 0.0 })
+    }
+
+    fn sort_key(&self) -> (u8, u32, bool, bool) {
+        match self {
+            Self::Known { base, plus } => (0, *base, *plus, false),
+            Self::Tentative { base, plus } => (0, *base, *plus, true),
+            Self::Unknown => (1, 0, false, false),
+        }
+    }
+}
+
+impl From<u32> for Level {
+    fn from(base: u32) -> Self {
+        Self::Known { base, plus: false }
+    }
+}
+
+impl Ord for Level {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.sort_key().cmp(&other.sort_key())
+    }
+}
+
+impl PartialOrd for Level {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl fmt::Display for Level {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Known { base, plus } => {
+                write!(f, "{base}{}", if *plus { "+" } else { "" })
+            }
+            Self::Tentative { base, plus } => {
+                write!(f, "{base}{}?", if *plus { "+" } else { "" })
+            }
+            Self::Unknown => f.write_str("?"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseLevelError(String);
+
+impl fmt::Display for ParseLevelError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "unrecognized level '{}'", self.0)
+    }
+}
+
+impl std::error::Error for ParseLevelError {}
+
+impl FromStr for Level {
+    type Err = ParseLevelError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s == "?" {
+            return Ok(Self::Unknown);
+        }
+
+        let (rest, tentative) = match s.strip_suffix('?') {
+            Some(rest) => (rest, true),
+            None => (s, false),
+        };
+
+        let (digits, plus) = match rest.strip_suffix('+') {
+            Some(digits) => (digits, true),
+            None => (rest, false),
+        };
+
+        if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+            return Err(ParseLevelError(s.to_owned()));
+        }
+
+        let base = digits.parse().map_err(|_| ParseLevelError(s.to_owned()))?;
+
+        if tentative {
+            Ok(Self::Tentative { base, plus })
+        } else {
+            Ok(Self::Known { base, plus })
+        }
+    }
+}
+
+impl Serialize for Level {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Level {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(D::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_levels() {
+        assert_eq!(
+            "13".parse(),
+            Ok(Level::Known {
+                base: 13,
+                plus: false
+            })
+        );
+        assert_eq!(
+            "13+".parse(),
+            Ok(Level::Known {
+                base: 13,
+                plus: true
+            })
+        );
+        assert_eq!(
+            "13+?".parse(),
+            Ok(Level::Tentative {
+                base: 13,
+                plus: true
+            })
+        );
+        assert_eq!(
+            "13?".parse(),
+            Ok(Level::Tentative {
+                base: 13,
+                plus: false
+            })
+        );
+        assert_eq!("?".parse(), Ok(Level::Unknown));
+    }
+
+    #[test]
+    fn rejects_bad_levels() {
+        for input in [
+            "", "+", "13++", "?13", "13?+", "+13", "13 ", "1.5", "abc", "??",
+        ] {
+            assert_eq!(
+                input.parse::<Level>(),
+                Err(ParseLevelError(input.to_owned())),
+                "{input:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn orders_levels() {
+        let levels: Vec<Level> = ["13", "13+", "13+?", "14", "?"]
+            .into_iter()
+            .map(|level| level.parse().unwrap())
+            .collect();
+
+        for pair in levels.windows(2) {
+            assert!(pair[0] < pair[1], "{} < {}", pair[0], pair[1]);
+        }
+
+        let mut shuffled = vec![levels[4], levels[2], levels[0], levels[3], levels[1]];
+        shuffled.sort();
+        assert_eq!(shuffled, levels);
+    }
+
+    #[test]
+    fn numeric_counts_plus_as_half() {
+        assert_eq!(Level::from(13).numeric(), Some(13.0));
+        assert_eq!("13+?".parse::<Level>().unwrap().numeric(), Some(13.5));
+        assert_eq!(Level::Unknown.numeric(), None);
+    }
+
+    #[test]
+    fn round_trips_through_toml() {
+        #[derive(serde::Serialize, serde::Deserialize)]
+        struct Chart {
+            level: Level,
+        }
+
+        for input in ["13", "13+", "13+?", "?", "1", "15?"] {
+            let document = format!("level = \"{input}\"\n");
+            let chart: Chart = toml::from_str(&document).unwrap();
+
+            assert_eq!(chart.level.to_string(), input);
+            assert_eq!(toml::to_string(&chart).unwrap(), document);
+        }
+    }
+}
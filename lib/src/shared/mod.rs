@@ -1,2 +1,14 @@
+pub mod availability;
+pub mod changelog;
 pub(crate) mod deserializers;
+pub mod diff;
+pub mod ids;
+pub mod jackets;
+pub mod level;
+pub mod query;
+pub mod registry;
 pub mod traits;
+pub mod unified;
+
+#[cfg(test)]
+pub(crate) mod test_songs;
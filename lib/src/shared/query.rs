@@ -0,0 +1,262 @@
+use std::ops::{Bound, RangeBounds};
+
+use super::level::Level;
+use super::traits::{Song as SongTrait, SongFlag};
+
+enum Filter<'a> {
+    Category(&'a str),
+    Version(&'a str),
+    Level {
+        difficulty: &'a str,
+        start: Bound<Level>,
+        end: Bound<Level>,
+    },
+    Text(String),
+    Flag(SongFlag, bool),
+}
+
+impl Filter<'_> {
+    fn matches<S>(&self, song: &S) -> bool
+    where
+        S: SongTrait,
+    {
+        match self {
+            Self::Category(category) => song.categories().contains(category),
+            Self::Version(version) => song.versions().contains(version),
+            Self::Level {
+                difficulty,
+                start,
+                end,
+            } => song
+                .charts()
+                .into_iter()
+                .any(|(d, level)| d == *difficulty && (*start, *end).contains(&level)),
+            Self::Text(needle) => [
+                Some(song.title()),
+                Some(song.artist()),
+                song.title_reading(),
+            ]
+            .into_iter()
+            .flatten()
+            .any(|haystack| haystack.to_lowercase().contains(needle.as_str())),
+            Self::Flag(flag, expected) => song.has_flag(*flag) == *expected,
+        }
+    }
+}
+
+/// Filters the songs of a data store, see [`DataStore::query`](super::traits::DataStore::query).
+///
+/// All filters must match for a song to be returned. Removed songs (tombstones) are skipped
+/// unless [`Query::include_removed`] is set.
+pub struct Query<'a, S> {
+    songs: &'a [S],
+    filters: Vec<Filter<'a>>,
+    include_removed: bool,
+}
+
+impl<'a, S> Query<'a, S>
+where
+    S: SongTrait,
+{
+    pub fn new(songs: &'a [S]) -> Self {
+        Self {
+            songs,
+            filters: vec![],
+            include_removed: false,
+        }
+    }
+
+    /// Matches any of the category ids, slugs or names of the song
+    pub fn category(mut self, category: &'a str) -> Self {
+        self.filters.push(Filter::Category(category));
+        self
+    }
+
+    /// Matches the exact version code or full name of the song, e.g. `"maimaiでらっくす BUDDiES"`
+    pub fn version(mut self, version: &'a str) -> Self {
+        self.filters.push(Filter::Version(version));
+        self
+    }
+
+    /// Matches songs with a chart of `difficulty` (e.g. `"master"`) within `range`
+    pub fn level<R>(mut self, difficulty: &'a str, range: R) -> Self
+    where
+        R: RangeBounds<Level>,
+    {
+        self.filters.push(Filter::Level {
+            difficulty,
+            start: range.start_bound().cloned(),
+            end: range.end_bound().cloned(),
+        });
+        self
+    }
+
+    /// Case-insensitive substring match on the title, artist or title reading
+    pub fn text(mut self, needle: &str) -> Self {
+        self.filters.push(Filter::Text(needle.to_lowercase()));
+        self
+    }
+
+    pub fn flag(mut self, flag: SongFlag, expected: bool) -> Self {
+        self.filters.push(Filter::Flag(flag, expected));
+        self
+    }
+
+    pub fn include_removed(mut self) -> Self {
+        self.include_removed = true;
+        self
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &'a S> + '_ {
+        self.songs.iter().filter(|song| {
+            (self.include_removed || !song.is_removed())
+                && self.filters.iter().all(|filter| filter.matches(*song))
+        })
+    }
+
+    pub fn to_vec(&self) -> Vec<&'a S> {
+        self.iter().collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::maimai::models::Song;
+    use crate::shared::test_songs::songs;
+
+    use super::*;
+
+    const SONGS: &str = r#"
+[[songs]]
+id = "1"
+title = "Love You"
+title_reading = "LOVEYOU"
+artist = "Q;indivi"
+image_id = "1.png"
+category = "POPS＆アニメ"
+version = "24005"
+is_new = true
+is_locked = false
+
+[songs.dx_levels]
+basic = "3"
+master = "13+"
+
+[[songs]]
+id = "2"
+title = "ガラテアの螺旋"
+title_reading = "カラテアノラセン"
+artist = "seatrus"
+image_id = "2.png"
+category = "maimai"
+version = "13000"
+is_new = false
+is_locked = true
+
+[songs.levels]
+basic = "5"
+master = "14"
+
+[[songs]]
+id = "3"
+title = "Removed"
+title_reading = "REMOVED"
+artist = "Nobody"
+image_id = "3.png"
+category = "maimai"
+version = "23000"
+is_new = false
+is_locked = false
+removed_at = "2026-08-01"
+
+[songs.levels]
+master = "14"
+"#;
+
+    fn ids(query: &Query<'_, Song>) -> Vec<String> {
+        query.iter().map(|song| song.key().into_owned()).collect()
+    }
+
+    fn level(level: &str) -> Level {
+        level.parse().unwrap()
+    }
+
+    #[test]
+    fn filters_by_category() {
+        let songs: Vec<Song> = songs(SONGS);
+        assert_eq!(ids(&Query::new(&songs).category("maimai")), ["2"]);
+        assert_eq!(
+            ids(&Query::new(&songs).category("オンゲキ")),
+            Vec::<String>::new()
+        );
+    }
+
+    #[test]
+    fn filters_by_version_code_or_name() {
+        let songs: Vec<Song> = songs(SONGS);
+        assert_eq!(ids(&Query::new(&songs).version("24005")), ["1"]);
+        assert_eq!(
+            ids(&Query::new(&songs).version("maimaiでらっくす BUDDiES")),
+            ["1"]
+        );
+        assert_eq!(ids(&Query::new(&songs).version("maimai GreeN PLUS")), ["2"]);
+    }
+
+    #[test]
+    fn filters_by_level_range() {
+        let songs: Vec<Song> = songs(SONGS);
+        assert_eq!(
+            ids(&Query::new(&songs).level("master", level("14")..)),
+            ["2"]
+        );
+        assert_eq!(
+            ids(&Query::new(&songs).level("dx_master", level("13")..=level("13+"))),
+            ["1"]
+        );
+        assert_eq!(
+            ids(&Query::new(&songs).level("master", ..level("14"))),
+            Vec::<String>::new()
+        );
+    }
+
+    #[test]
+    fn filters_by_text() {
+        let songs: Vec<Song> = songs(SONGS);
+        assert_eq!(ids(&Query::new(&songs).text("love")), ["1"]);
+        assert_eq!(ids(&Query::new(&songs).text("SEATRUS")), ["2"]);
+        assert_eq!(ids(&Query::new(&songs).text("ラセン")), ["2"]);
+    }
+
+    #[test]
+    fn filters_by_flag() {
+        let songs: Vec<Song> = songs(SONGS);
+        assert_eq!(ids(&Query::new(&songs).flag(SongFlag::New, true)), ["1"]);
+        assert_eq!(ids(&Query::new(&songs).flag(SongFlag::Locked, true)), ["2"]);
+        assert_eq!(
+            ids(&Query::new(&songs).flag(SongFlag::Locked, false)),
+            ["1"]
+        );
+    }
+
+    #[test]
+    fn skips_removed_songs_unless_asked() {
+        let songs: Vec<Song> = songs(SONGS);
+        assert_eq!(ids(&Query::new(&songs)), ["1", "2"]);
+        assert_eq!(ids(&Query::new(&songs).include_removed()), ["1", "2", "3"]);
+        assert_eq!(
+            ids(&Query::new(&songs).include_removed().version("23000")),
+            ["3"]
+        );
+    }
+
+    #[test]
+    fn combines_filters() {
+        let songs: Vec<Song> = songs(SONGS);
+        let query = Query::new(&songs)
+            .category("maimai")
+            .level("master", level("14")..);
+
+        assert_eq!(ids(&query), ["2"]);
+        assert_eq!(ids(&query.text("love")), Vec::<String>::new());
+    }
+}
@@ -0,0 +1,84 @@
+use super::traits::Otoge;
+
+/// Runs the block once for every game, with the given identifier bound to the game's type.
+///
+/// This is the single list of games: binaries iterate over it instead of naming each game, so
+/// every tool picks up a new game as soon as it's added here. The order is the one tasks are
+/// started and reported in.
+///
+/// ```
+/// use otoge::shared::traits::Otoge;
+///
+/// let mut names = vec![];
+/// otoge::for_each_game!(|G| {
+///     names.push(G::name());
+/// });
+/// assert!(names.contains(&"polarischord"));
+/// ```
+#[macro_export]
+macro_rules! for_each_game {
+    (|$game:ident| $body:block) => {{
+        {
+            type $game = $crate::soundvoltex::SoundVoltex;
+            $body
+        }
+        {
+            type $game = $crate::popnmusic::PopNMusic;
+            $body
+        }
+        {
+            type $game = $crate::chunithm::ChunithmJP;
+            $body
+        }
+        {
+            type $game = $crate::chunithm::ChunithmIntl;
+            $body
+        }
+        {
+            type $game = $crate::ongeki::Ongeki;
+            $body
+        }
+        {
+            type $game = $crate::maimai::MaimaiJP;
+            $body
+        }
+        {
+            type $game = $crate::maimai::MaimaiIntl;
+            $body
+        }
+        {
+            type $game = $crate::polarischord::PolarisChord;
+            $body
+        }
+    }};
+}
+
+/// Metadata of a game, for code that doesn't need its types
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GameInfo {
+    pub name: &'static str,
+    pub display_name: &'static str,
+    pub homepage: &'static str,
+}
+
+impl GameInfo {
+    pub fn of<G>() -> Self
+    where
+        G: Otoge,
+    {
+        Self {
+            name: G::name(),
+            display_name: G::display_name(),
+            homepage: G::homepage(),
+        }
+    }
+}
+
+/// Every game in [`for_each_game!`] order
+pub fn all_games() -> Vec<GameInfo> {
+    let mut games = vec![];
+    crate::for_each_game!(|G| {
+        games.push(GameInfo::of::<G>());
+    });
+    games
+}
@@ -0,0 +1,37 @@
+//! Songs for the unit tests, written like the `[[songs]]` of a `music.toml` data store
+
+use serde::Deserialize;
+use serde::de::DeserializeOwned;
+
+#[derive(Deserialize)]
+#[serde(bound = "S: DeserializeOwned")]
+struct Songs<S> {
+    #[serde(default = "Vec::new")]
+    songs: Vec<S>,
+}
+
+pub(crate) fn songs<S>(toml: &str) -> Vec<S>
+where
+    S: DeserializeOwned,
+{
+    toml::from_str::<Songs<S>>(toml).unwrap().songs
+}
+
+/// CHUNITHM song with only a master chart
+pub(crate) fn chunithm_song(id: &str, title: &str, master: &str) -> String {
+    format!(
+        r#"
+[[songs]]
+id = "{id}"
+title = "{title}"
+title_reading = "{title}"
+artist = "Artist"
+image_id = "{id}.jpg"
+category = "ORIGINAL"
+is_new = false
+
+[songs.levels]
+master = "{master}"
+"#
+    )
+}
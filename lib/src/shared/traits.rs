@@ -1,10 +1,23 @@
+use std::borrow::Cow;
 use std::path::{Path, PathBuf};
 
+use chrono::{DateTime, NaiveDate, Utc};
+
+use super::diff::{DataStoreDiff, SongFields};
+use super::level::Level;
+use super::query::Query;
+use super::unified::UnifiedSong;
+
 pub trait Otoge {
     type DataStore;
     type Song;
 
     fn name() -> &'static str;
+    /// Human-readable name, including the region for games released in several
+    fn display_name() -> &'static str;
+    /// Official website of the game
+    fn homepage() -> &'static str;
+
     fn data_path(base_path: Option<&Path>) -> PathBuf {
         let path = base_path.unwrap_or(Path::new(""));
         path.join(Self::name())
@@ -13,8 +26,201 @@ pub trait Otoge {
     fn music_data_store_path(base_path: Option<&Path>) -> PathBuf {
         Self::data_path(base_path).join("music.toml")
     }
+
+    fn changelog_path(base_path: Option<&Path>) -> PathBuf {
+        Self::data_path(base_path).join("changelog.toml")
+    }
+
+    fn id_registry_path(base_path: Option<&Path>) -> PathBuf {
+        Self::data_path(base_path).join("ids.toml")
+    }
+
+    /// Directory of the local jacket mirror, see [`super::jackets::JacketManifest`]
+    fn jackets_path(base_path: Option<&Path>) -> PathBuf {
+        Self::data_path(base_path).join("jackets")
+    }
+
+    fn jacket_manifest_path(base_path: Option<&Path>) -> PathBuf {
+        Self::jackets_path(base_path).join("manifest.toml")
+    }
+
+    /// Prefix turning an image id into an absolute jacket URL, `None` for games without jackets
+    fn jacket_base_url() -> Option<&'static str> {
+        None
+    }
+
+    /// Absolute URL of the jacket image with the given [`Song::image_id`]
+    ///
+    /// ```
+    /// use otoge::chunithm::{ChunithmIntl, ChunithmJP};
+    /// use otoge::maimai::{MaimaiIntl, MaimaiJP};
+    /// use otoge::ongeki::Ongeki;
+    /// use otoge::polarischord::PolarisChord;
+    /// use otoge::popnmusic::PopNMusic;
+    /// use otoge::shared::traits::Otoge;
+    /// use otoge::soundvoltex::SoundVoltex;
+    ///
+    /// assert_eq!(
+    ///     ChunithmJP::jacket_url("b867b55593a36114.jpg").as_deref(),
+    ///     Some("https://new.chunithm-net.com/chuni-mobile/html/mobile/img/b867b55593a36114.jpg")
+    /// );
+    /// assert_eq!(
+    ///     ChunithmIntl::jacket_url("86a52dcd3a3c1190.jpg").as_deref(),
+    ///     Some("https://chunithm-net-eng.com/mobile/img/86a52dcd3a3c1190.jpg")
+    /// );
+    /// assert_eq!(
+    ///     MaimaiJP::jacket_url("c7cfd8a91e0436ac.png").as_deref(),
+    ///     Some("https://maimai.sega.jp/storage/DX_jacket/c7cfd8a91e0436ac.png")
+    /// );
+    /// assert_eq!(
+    ///     MaimaiIntl::jacket_url("c7cfd8a91e0436ac.png").as_deref(),
+    ///     Some("https://maimai.sega.com/assets/img/download/jacket/c7cfd8a91e0436ac.png")
+    /// );
+    /// assert_eq!(
+    ///     Ongeki::jacket_url("5a9758493362722b.png").as_deref(),
+    ///     Some("https://ongeki.sega.jp/assets/img/music/5a9758493362722b.png")
+    /// );
+    /// assert_eq!(
+    ///     PopNMusic::jacket_url("/game/popn/popn29/images/img_binary.html?img=H/Nn4N6T").as_deref(),
+    ///     Some("https://p.eagate.573.jp/game/popn/popn29/images/img_binary.html?img=H/Nn4N6T")
+    /// );
+    /// assert_eq!(
+    ///     SoundVoltex::jacket_url("/game/sdvx/vi/common/jacket.html?img=DR3HZtoGQC1vDqdvYlL9Cg")
+    ///         .as_deref(),
+    ///     Some("https://p.eagate.573.jp/game/sdvx/vi/common/jacket.html?img=DR3HZtoGQC1vDqdvYlL9Cg")
+    /// );
+    /// assert_eq!(PolarisChord::jacket_url("anything"), None);
+    /// ```
+    fn jacket_url(image_id: &str) -> Option<String> {
+        Self::jacket_base_url().map(|base| format!("{base}{image_id}"))
+    }
+
+    fn song_jacket_url(song: &Self::Song) -> Option<String>
+    where
+        Self::Song: Song,
+    {
+        song.image_id().and_then(Self::jacket_url)
+    }
+
+    fn unify(song: &Self::Song) -> UnifiedSong
+    where
+        Self::Song: Song,
+    {
+        UnifiedSong::from_song(Self::name(), song)
+    }
 }
 
 pub trait DataStore {
-    fn data_differs(&self, other: &Self) -> bool;
+    type Song: Song;
+
+    fn name(&self) -> &str;
+
+    fn songs(&self) -> &[Self::Song];
+
+    /// Every difficulty of the game in the order the game lists them, whether charted or not
+    fn difficulties(&self) -> Vec<&'static str> {
+        Self::Song::DIFFICULTIES.to_vec()
+    }
+
+    /// Time the data store was last written by the fetcher
+    fn last_updated(&self) -> DateTime<Utc>;
+
+    /// Whether the category/version definitions differ, independent of the songs
+    fn definitions_differ(&self, other: &Self) -> bool;
+
+    fn diff(&self, other: &Self) -> DataStoreDiff {
+        DataStoreDiff::between(self.songs(), other.songs(), self.definitions_differ(other))
+    }
+
+    fn data_differs(&self, other: &Self) -> bool {
+        !self.diff(other).is_empty()
+    }
+
+    /// Songs currently available upstream, excluding tombstones
+    fn available_songs(&self) -> impl Iterator<Item = &Self::Song> {
+        self.songs().iter().filter(|song| !song.is_removed())
+    }
+
+    fn songs_available_on(&self, date: NaiveDate) -> impl Iterator<Item = &Self::Song> {
+        self.songs()
+            .iter()
+            .filter(move |song| song.is_available_on(date))
+    }
+
+    fn query(&self) -> Query<'_, Self::Song> {
+        Query::new(self.songs())
+    }
+
+    fn unified_songs(&self) -> Vec<UnifiedSong> {
+        self.songs()
+            .iter()
+            .map(|song| UnifiedSong::from_song(self.name().to_owned(), song))
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SongFlag {
+    New,
+    Locked,
+    BonusTrack,
+    Lunatic,
+}
+
+pub trait Song {
+    /// Difficulty names used by [`Song::charts`], in the order the game lists them
+    const DIFFICULTIES: &'static [&'static str];
+
+    /// Identity of the song, used to match songs between two data stores
+    fn key(&self) -> Cow<'_, str>;
+    fn title(&self) -> &str;
+    fn artist(&self) -> &str;
+    fn title_reading(&self) -> Option<&str> {
+        None
+    }
+
+    /// Ids, slugs and names of the categories the song belongs to
+    fn categories(&self) -> Vec<&str>;
+    /// Display names of the categories the song belongs to
+    fn category_names(&self) -> Vec<&str>;
+    /// Code and name of the version the song was introduced in, if the game has versions
+    fn versions(&self) -> Vec<&str> {
+        vec![]
+    }
+
+    /// Game-specific reference to the jacket image, see [`Otoge::jacket_url`]
+    fn image_id(&self) -> Option<&str> {
+        None
+    }
+
+    /// Difficulty name and level of every chart of the song
+    fn charts(&self) -> Vec<(&'static str, Level)>;
+
+    fn has_flag(&self, _flag: SongFlag) -> bool {
+        false
+    }
+
+    /// Upstream release date, for the games that publish one
+    fn release_date(&self) -> Option<NaiveDate> {
+        None
+    }
+
+    fn fields(&self) -> SongFields;
+
+    /// Date the song was first seen by the fetcher, `None` if it predates the tracking
+    fn first_seen(&self) -> Option<NaiveDate>;
+    /// Date the song disappeared upstream, set on songs kept as tombstones
+    fn removed_at(&self) -> Option<NaiveDate>;
+    fn set_first_seen(&mut self, date: Option<NaiveDate>);
+    fn set_removed_at(&mut self, date: Option<NaiveDate>);
+
+    fn is_removed(&self) -> bool {
+        self.removed_at().is_some()
+    }
+
+    fn is_available_on(&self, date: NaiveDate) -> bool {
+        self.first_seen()
+            .is_none_or(|first_seen| first_seen <= date)
+            && self.removed_at().is_none_or(|removed_at| removed_at > date)
+    }
 }
@@ -0,0 +1,182 @@
+use std::borrow::Cow;
+
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+
+use super::level::Level;
+use super::traits::Song as SongTrait;
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct UnifiedChart {
+    pub difficulty: Cow<'static, str>,
+    pub level: Level,
+}
+
+/// Game-agnostic view of a song, for tools handling songs from every data store alike
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct UnifiedSong {
+    pub game: Cow<'static, str>,
+    pub id: String,
+    pub title: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title_reading: Option<String>,
+    pub artist: String,
+    pub categories: Vec<String>,
+    pub charts: Vec<UnifiedChart>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub release: Option<NaiveDate>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub first_seen: Option<NaiveDate>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub removed_at: Option<NaiveDate>,
+}
+
+impl UnifiedSong {
+    pub fn from_song<S>(game: impl Into<Cow<'static, str>>, song: &S) -> Self
+    where
+        S: SongTrait,
+    {
+        Self {
+            game: game.into(),
+            id: song.key().into_owned(),
+            title: song.title().to_owned(),
+            title_reading: song.title_reading().map(str::to_owned),
+            artist: song.artist().to_owned(),
+            categories: song
+                .category_names()
+                .into_iter()
+                .map(str::to_owned)
+                .collect(),
+            charts: song
+                .charts()
+                .into_iter()
+                .map(|(difficulty, level)| UnifiedChart {
+                    difficulty: Cow::Borrowed(difficulty),
+                    level,
+                })
+                .collect(),
+            release: song.release_date(),
+            first_seen: song.first_seen(),
+            removed_at: song.removed_at(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::maimai::MaimaiJP;
+    use crate::popnmusic::PopNMusic;
+    use crate::shared::test_songs::songs;
+    use crate::shared::traits::Otoge;
+
+    use super::*;
+
+    fn chart(difficulty: &'static str, level: &str) -> UnifiedChart {
+        UnifiedChart {
+            difficulty: Cow::Borrowed(difficulty),
+            level: level.parse().unwrap(),
+        }
+    }
+
+    #[test]
+    fn maps_maimai_songs() {
+        let songs: Vec<crate::maimai::models::Song> = songs(
+            r#"
+[[songs]]
+id = "1644"
+title = "[協]Love You"
+title_reading = "LOVEYOU"
+artist = "Q;indivi [cover]"
+image_id = "daf6adeacb8fc300.png"
+category = "宴会場"
+version = "24000"
+release = "2023-09-14"
+is_new = false
+is_locked = false
+first_seen = "2026-08-01"
+
+[songs.dx_levels]
+basic = "3"
+master = "13+"
+
+[songs.utage]
+level = "12?"
+kanji = "協"
+comment = "リア充専用譜面だよ！リア充向けだってば！"
+"#,
+        );
+
+        assert_eq!(
+            MaimaiJP::unify(&songs[0]),
+            UnifiedSong {
+                game: Cow::Borrowed("maimai_jp"),
+                id: "1644".to_owned(),
+                title: "[協]Love You".to_owned(),
+                title_reading: Some("LOVEYOU".to_owned()),
+                artist: "Q;indivi [cover]".to_owned(),
+                categories: vec!["宴会場".to_owned()],
+                charts: vec![
+                    chart("dx_basic", "3"),
+                    chart("dx_master", "13+"),
+                    chart("utage", "12?"),
+                ],
+                release: NaiveDate::from_ymd_opt(2023, 9, 14),
+                first_seen: NaiveDate::from_ymd_opt(2026, 8, 1),
+                removed_at: None,
+            }
+        );
+    }
+
+    #[test]
+    fn maps_popn_songs() {
+        let songs: Vec<crate::popnmusic::models::Song> = songs(
+            r#"
+[[songs]]
+id = "1"
+image_id = "/game/popn/popn29/images/img_binary.html?img=H/Nn4N6T"
+genre = "*ハロー、プラネット。"
+title = "*ハロー、プラネット。"
+artist = "sasakure.UK"
+
+[songs.levels]
+light = "9"
+normal = "23"
+hyper = "35"
+ex = "42"
+
+[songs.version]
+id = "25"
+name = "pop'n music peace"
+
+[[songs.recommendation_categories]]
+id = "1"
+name = "オススメ"
+
+[[songs.recommendation_categories]]
+id = "7"
+name = "NET MUSIC・VOCALOID"
+"#,
+        );
+
+        assert_eq!(
+            PopNMusic::unify(&songs[0]),
+            UnifiedSong {
+                game: Cow::Borrowed("popnmusic"),
+                id: "1".to_owned(),
+                title: "*ハロー、プラネット。".to_owned(),
+                title_reading: None,
+                artist: "sasakure.UK".to_owned(),
+                categories: vec!["オススメ".to_owned(), "NET MUSIC・VOCALOID".to_owned()],
+                charts: vec![
+                    chart("light", "9"),
+                    chart("normal", "23"),
+                    chart("hyper", "35"),
+                    chart("ex", "42"),
+                ],
+                release: None,
+                first_seen: None,
+                removed_at: None,
+            }
+        );
+    }
+}
@@ -14,6 +14,18 @@ impl Otoge for SoundVoltex {
     fn name() -> &'static str {
         "soundvoltex"
     }
+
+    fn display_name() -> &'static str {
+        "SOUND VOLTEX"
+    }
+
+    fn homepage() -> &'static str {
+        "https://p.eagate.573.jp/game/sdvx/vii/"
+    }
+
+    fn jacket_base_url() -> Option<&'static str> {
+        Some("https://p.eagate.573.jp")
+    }
 }
 
 pub(crate) fn get_all_categories() -> Vec<Category> {
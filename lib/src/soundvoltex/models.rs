@@ -1,51 +1,223 @@
 use std::borrow::Cow;
 
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, NaiveDate, Utc};
 use serde::{Deserialize, Serialize};
 
 use super::get_all_categories;
-use crate::shared::traits::DataStore as DataStoreTrait;
+use crate::shared::diff::SongFields;
+use crate::shared::ids::SyntheticId;
+use crate::shared::level::Level;
+use crate::shared::traits::{DataStore as DataStoreTrait, Song as SongTrait};
 
 #[allow(dead_code)]
-#[derive(Serialize, Deserialize, Debug, Default, PartialEq)]
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
 pub struct LevelMap {
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub novice: Option<String>,
+    pub novice: Option<Level>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub advanced: Option<String>,
+    pub advanced: Option<Level>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub exhaust: Option<String>,
+    pub exhaust: Option<Level>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub maximum: Option<String>,
+    pub maximum: Option<Level>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub infinite: Option<String>,
+    pub infinite: Option<Level>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub gravity: Option<String>,
+    pub gravity: Option<Level>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub heavenly: Option<String>,
+    pub heavenly: Option<Level>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub vivid: Option<String>,
+    pub vivid: Option<Level>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub exceed: Option<String>,
+    pub exceed: Option<Level>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub ultimate: Option<String>,
+    pub ultimate: Option<Level>,
 }
 
-#[derive(Debug, Serialize, Deserialize, PartialEq)]
+impl LevelMap {
+    /// Difficulty names in the order the game lists them
+    pub const DIFFICULTIES: [&'static str; 10] = [
+        "novice", "advanced", "exhaust", "maximum", "infinite", "gravity", "heavenly", "vivid",
+        "exceed", "ultimate",
+    ];
+
+    pub fn charts(&self) -> Vec<(&'static str, Level)> {
+        Self::DIFFICULTIES
+            .into_iter()
+            .zip([
+                self.novice,
+                self.advanced,
+                self.exhaust,
+                self.maximum,
+                self.infinite,
+                self.gravity,
+                self.heavenly,
+                self.vivid,
+                self.exceed,
+                self.ultimate,
+            ])
+            .filter_map(|(difficulty, level)| level.map(|level| (difficulty, level)))
+            .collect()
+    }
+}
+
+/// Builds a level map from `(difficulty, level)` pairs as returned by [`LevelMap::charts`],
+/// unknown difficulties are ignored
+impl<'a> FromIterator<(&'a str, Level)> for LevelMap {
+    fn from_iter<I>(iter: I) -> Self
+    where
+        I: IntoIterator<Item = (&'a str, Level)>,
+    {
+        let mut levels = Self::default();
+        for (difficulty, level) in iter {
+            match difficulty {
+                "novice" => levels.novice = Some(level),
+                "advanced" => levels.advanced = Some(level),
+                "exhaust" => levels.exhaust = Some(level),
+                "maximum" => levels.maximum = Some(level),
+                "infinite" => levels.infinite = Some(level),
+                "gravity" => levels.gravity = Some(level),
+                "heavenly" => levels.heavenly = Some(level),
+                "vivid" => levels.vivid = Some(level),
+                "exceed" => levels.exceed = Some(level),
+                "ultimate" => levels.ultimate = Some(level),
+                _ => {}
+            }
+        }
+        levels
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct Category {
     pub id: Cow<'static, str>,
     pub name: Cow<'static, str>,
 }
 
-#[derive(Debug, Serialize, Deserialize, PartialEq)]
+/// Credits of a single chart, from the song's detail page
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ChartDetails {
+    /// Difficulty name as used in [`LevelMap::charts`]
+    pub difficulty: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub effector: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub illustrator: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct Song {
-    // FIXME: id changes regularly
-    // pub id: String,
+    /// otoge-assigned id, the upstream music_id changes regularly
+    pub id: String,
     pub image_id: String,
     pub title: String,
     pub artist: String,
+    /// Only known once the detail page has been fetched, may be a range like "100-200"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bpm: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub first_seen: Option<NaiveDate>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub removed_at: Option<NaiveDate>,
     pub categories: Vec<Category>,
     pub levels: LevelMap,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub charts: Vec<ChartDetails>,
+    /// Detail page of the song as of the current fetch, not persisted since it changes regularly
+    #[serde(skip)]
+    pub detail_url: Option<String>,
+}
+
+impl Song {
+    pub fn has_details(&self) -> bool {
+        self.bpm.is_some() || !self.charts.is_empty()
+    }
+}
+
+impl SongTrait for Song {
+    const DIFFICULTIES: &'static [&'static str] = &LevelMap::DIFFICULTIES;
+
+    fn key(&self) -> Cow<'_, str> {
+        Cow::Borrowed(&self.id)
+    }
+
+    fn title(&self) -> &str {
+        &self.title
+    }
+
+    fn artist(&self) -> &str {
+        &self.artist
+    }
+
+    fn image_id(&self) -> Option<&str> {
+        Some(&self.image_id)
+    }
+
+    fn categories(&self) -> Vec<&str> {
+        self.categories
+            .iter()
+            .flat_map(|c| [c.id.as_ref(), c.name.as_ref()])
+            .collect()
+    }
+
+    fn category_names(&self) -> Vec<&str> {
+        self.categories.iter().map(|c| c.name.as_ref()).collect()
+    }
+
+    fn charts(&self) -> Vec<(&'static str, Level)> {
+        self.levels.charts()
+    }
+
+    fn fields(&self) -> SongFields {
+        let fields = SongFields::default()
+            .field("title", &self.title)
+            .field("artist", &self.artist)
+            .field("image_id", &self.image_id)
+            .list_field("categories", self.categories.iter().map(|c| &c.id))
+            .levels("levels", self.levels.charts())
+            .optional_field("bpm", self.bpm.as_ref());
+
+        self.charts.iter().fold(fields, |fields, chart| {
+            fields
+                .optional_field(
+                    format!("charts.{}.effector", chart.difficulty),
+                    chart.effector.as_ref(),
+                )
+                .optional_field(
+                    format!("charts.{}.illustrator", chart.difficulty),
+                    chart.illustrator.as_ref(),
+                )
+        })
+    }
+
+    fn first_seen(&self) -> Option<NaiveDate> {
+        self.first_seen
+    }
+
+    fn removed_at(&self) -> Option<NaiveDate> {
+        self.removed_at
+    }
+
+    fn set_first_seen(&mut self, date: Option<NaiveDate>) {
+        self.first_seen = date;
+    }
+
+    fn set_removed_at(&mut self, date: Option<NaiveDate>) {
+        self.removed_at = date;
+    }
+}
+
+impl SyntheticId for Song {
+    fn fingerprints(&self) -> Vec<String> {
+        vec![
+            format!("{}\t{}", self.title, self.artist),
+            self.image_id.clone(),
+        ]
+    }
+
+    fn set_id(&mut self, id: String) {
+        self.id = id;
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -61,7 +233,7 @@ impl DataStore {
     pub fn new(name: &'static str, songs: Vec<Song>) -> Self {
         Self {
             name: Cow::Borrowed(name),
-            count: songs.len(),
+            count: songs.iter().filter(|song| !song.is_removed()).count(),
             songs,
             last_updated: Utc::now(),
             categories: get_all_categories(),
@@ -70,9 +242,21 @@ impl DataStore {
 }
 
 impl DataStoreTrait for DataStore {
-    fn data_differs(&self, other: &Self) -> bool {
-        self.count != other.count
-            || !self.songs.iter().eq(other.songs.iter())
-            || !self.categories.iter().eq(other.categories.iter())
+    type Song = Song;
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn songs(&self) -> &[Song] {
+        &self.songs
+    }
+
+    fn last_updated(&self) -> DateTime<Utc> {
+        self.last_updated
+    }
+
+    fn definitions_differ(&self, other: &Self) -> bool {
+        !self.categories.iter().eq(other.categories.iter())
     }
 }